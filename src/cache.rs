@@ -0,0 +1,386 @@
+use std::{
+    fs::{self, create_dir_all, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AttestError;
+use crate::problem::ProblemMeta;
+use crate::task::Task;
+use crate::test::IO;
+use crate::utils::{create_err, global_dir, now_secs, write_err};
+
+// Examples, time limit and metadata of the task
+#[derive(Debug, Clone)]
+pub struct TaskCache {
+    pub examples: Vec<IO>,
+    pub time_limit: u128,
+    pub meta: ProblemMeta,
+    // HTML of the problem statement
+    pub statement: String,
+}
+
+// Version of the format of the task cache, raised when fields are added to it
+//
+// Caches of older versions are downloaded again, since they lack the new fields
+pub const TASK_CACHE_VERSION: u32 = 1;
+
+// Content of `meta.json`
+#[derive(Serialize, Deserialize)]
+struct MetaFile {
+    // Caches written before the version was added have 0
+    #[serde(default)]
+    version: u32,
+    #[serde(flatten)]
+    meta: ProblemMeta,
+}
+
+// Number of builds whose artifacts are kept
+pub const ARTIFACT_CACHE_SIZE: usize = 8;
+
+// Directory to hold the cache of built artifacts
+const ARTIFACT_DIR: &str = "./.attest/artifacts";
+
+// Directory to hold the cache of the contest
+pub fn contest_dir(contest: &str) -> PathBuf {
+    PathBuf::from("./.attest/tasks").join(contest)
+}
+
+// Directory to hold the cache of the task
+pub fn task_dir(task: &Task) -> PathBuf {
+    contest_dir(&task.contest).join(&task.id)
+}
+
+// Directory to hold the cache of the task shared across directories
+pub fn global_task_dir(task: &Task) -> Result<PathBuf> {
+    Ok(global_dir()?
+        .join("cache")
+        .join(&task.contest)
+        .join(&task.id))
+}
+
+// Save examples, time limit, metadata and statement of the task
+pub fn save_task_cache(task: &Task, cache: &TaskCache) -> Result<()> {
+    save_task_cache_in(task_dir(task), cache)
+}
+
+// Save examples, time limit, metadata and statement of the task in the directory
+pub fn save_task_cache_in(dir: PathBuf, cache: &TaskCache) -> Result<()> {
+    create_dir_all(&dir).with_context(|| create_err(&dir))?;
+
+    let path: PathBuf = dir.join("meta.json");
+    let mut m: File = File::create(&path).with_context(|| create_err(&path))?;
+    let meta: MetaFile = MetaFile {
+        version: TASK_CACHE_VERSION,
+        meta: cache.meta.clone(),
+    };
+    write!(&mut m, "{}", serde_json::to_string(&meta).unwrap())
+        .with_context(|| write_err(&path))?;
+
+    let path: PathBuf = dir.join("time_limit.txt");
+    let mut t: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(&mut t, "{}", cache.time_limit).with_context(|| write_err(&path))?;
+
+    let path: PathBuf = dir.join("examples.json");
+    let mut e: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(
+        &mut e,
+        "{}",
+        serde_json::to_string(&cache.examples).unwrap()
+    )
+    .with_context(|| write_err(&path))?;
+
+    let path: PathBuf = dir.join("statement.html");
+    let mut s: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(&mut s, "{}", cache.statement).with_context(|| write_err(&path))?;
+
+    Ok(())
+}
+
+// Get examples, time limit and metadata of the task if they are cached
+pub fn task_cache(task: &Task) -> Option<TaskCache> {
+    task_cache_in(task_dir(task))
+}
+
+// Get the cache of the task even if its format is old, to use it when downloading is impossible
+pub fn old_task_cache(task: &Task) -> Option<TaskCache> {
+    read_task_cache(task_dir(task)).map(|(cache, _): (TaskCache, u32)| cache)
+}
+
+// Get the cache of the task shared across directories if it is newer than `ttl` seconds
+pub fn global_task_cache(task: &Task, ttl: u64) -> Option<TaskCache> {
+    let cache: TaskCache = task_cache_in(global_task_dir(task).ok()?)?;
+
+    if now_secs().saturating_sub(cache.meta.fetched_at) > ttl {
+        return None;
+    }
+
+    Some(cache)
+}
+
+// Get examples, time limit and metadata of the task if they are cached in the directory
+pub fn task_cache_in(dir: PathBuf) -> Option<TaskCache> {
+    read_task_cache(dir)
+        .filter(|(_, version): &(TaskCache, u32)| *version >= TASK_CACHE_VERSION)
+        .map(|(cache, _): (TaskCache, u32)| cache)
+}
+
+// Read the cache of the task in the directory with the version of its format
+fn read_task_cache(dir: PathBuf) -> Option<(TaskCache, u32)> {
+    let examples: String = fs::read_to_string(dir.join("examples.json")).ok()?;
+
+    let time_limit: String = fs::read_to_string(dir.join("time_limit.txt")).ok()?;
+
+    let meta: String = fs::read_to_string(dir.join("meta.json")).ok()?;
+
+    let meta: MetaFile = serde_json::from_str(meta.trim()).ok()?;
+
+    let cache: TaskCache = TaskCache {
+        examples: serde_json::from_str(examples.trim()).ok()?,
+        time_limit: time_limit.trim().parse().ok()?,
+        meta: meta.meta,
+        statement: fs::read_to_string(dir.join("statement.html")).unwrap_or_default(),
+    };
+
+    Some((cache, meta.version))
+}
+
+// Save tasks of the contest
+pub fn save_task_list(contest: &str, list: &[(String, Task)]) -> Result<()> {
+    let dir: PathBuf = contest_dir(contest);
+
+    create_dir_all(&dir).with_context(|| create_err(&dir))?;
+
+    let path: PathBuf = dir.join("tasks.json");
+    let mut f: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(&mut f, "{}", serde_json::to_string(list).unwrap()).with_context(|| write_err(&path))?;
+
+    Ok(())
+}
+
+// Get tasks of the contest if they are cached
+pub fn task_list_cache(contest: &str) -> Option<Vec<(String, Task)>> {
+    let text: String = fs::read_to_string(contest_dir(contest).join("tasks.json")).ok()?;
+
+    serde_json::from_str(text.trim()).ok()
+}
+
+// Save the contest to resolve task aliases
+pub fn save_current_contest(contest: &str) -> Result<()> {
+    let mut f: File = File::create("./.attest/contest.txt")
+        .with_context(|| create_err("./.attest/contest.txt"))?;
+    write!(&mut f, "{}", contest).with_context(|| write_err("./.attest/contest.txt"))?;
+
+    Ok(())
+}
+
+// Get the contest to resolve task aliases
+pub fn current_contest() -> Option<String> {
+    let text: String = fs::read_to_string("./.attest/contest.txt").ok()?;
+
+    Some(text.trim().to_string()).filter(|s: &String| !s.is_empty())
+}
+
+// Save the task to test or submit when the URL is omitted
+pub fn save_current_task(task: &Task) -> Result<()> {
+    let mut f: File =
+        File::create("./.attest/task.json").with_context(|| create_err("./.attest/task.json"))?;
+    write!(&mut f, "{}", serde_json::to_string(task).unwrap())
+        .with_context(|| write_err("./.attest/task.json"))?;
+
+    Ok(())
+}
+
+// Get the task to test or submit when the URL is omitted
+pub fn current_task() -> Option<Task> {
+    let text: String = fs::read_to_string("./.attest/task.json").ok()?;
+
+    serde_json::from_str(text.trim()).ok()
+}
+
+// Path of the cache of langs shared across directories
+fn lang_cache_path() -> Result<PathBuf> {
+    Ok(global_dir()?.join("langs.json"))
+}
+
+// Save langs to use them offline
+pub fn save_lang_cache(langs: &[(String, String)]) -> Result<()> {
+    let path: PathBuf = lang_cache_path()?;
+
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir).with_context(|| create_err(dir))?;
+    }
+
+    let mut f: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(&mut f, "{}", serde_json::to_string(langs).unwrap())
+        .with_context(|| write_err(&path))?;
+
+    Ok(())
+}
+
+// Get langs if they are cached
+pub fn lang_cache() -> Option<Vec<(String, String)>> {
+    let text: String = fs::read_to_string(lang_cache_path().ok()?).ok()?;
+
+    serde_json::from_str(text.trim()).ok()
+}
+
+// Save the outputs of the build step in the directory named after the hash of its command and inputs,
+// and remove the least recently used artifacts beyond `ARTIFACT_CACHE_SIZE`
+pub fn save_artifact(hash: u64, outputs: &[String]) -> Result<()> {
+    save_artifact_in(Path::new(ARTIFACT_DIR), hash, outputs)
+}
+
+// Save the outputs of the build step in the artifact cache of the directory
+fn save_artifact_in(root: &Path, hash: u64, outputs: &[String]) -> Result<()> {
+    let dir: PathBuf = root.join(format!("{:016x}", hash));
+
+    create_dir_all(&dir).with_context(|| create_err(&dir))?;
+
+    for (i, output) in outputs.iter().enumerate() {
+        let path: PathBuf = dir.join(i.to_string());
+
+        fs::copy(output, &path).with_context(|| create_err(&path))?;
+    }
+
+    touch_artifact(&dir)?;
+
+    let mut artifacts: Vec<(u64, PathBuf)> = fs::read_dir(root)
+        .with_context(|| create_err(root))?
+        .flatten()
+        .map(|e: fs::DirEntry| {
+            let used: u64 = fs::read_to_string(e.path().join("used"))
+                .ok()
+                .and_then(|s: String| s.trim().parse().ok())
+                .unwrap_or(0);
+
+            (used, e.path())
+        })
+        .collect();
+
+    artifacts.sort_by(|a: &(u64, PathBuf), b: &(u64, PathBuf)| b.cmp(a));
+
+    for (_, old) in artifacts.iter().skip(ARTIFACT_CACHE_SIZE) {
+        fs::remove_dir_all(old).with_context(|| {
+            AttestError::Io(format!("something went wrong removing {}", old.display()))
+        })?;
+    }
+
+    Ok(())
+}
+
+// Copy the outputs of the build step back from the cache, and get whether they were cached
+pub fn restore_artifact(hash: u64, outputs: &[String]) -> Result<bool> {
+    restore_artifact_in(Path::new(ARTIFACT_DIR), hash, outputs)
+}
+
+// Copy the outputs of the build step back from the artifact cache of the directory
+fn restore_artifact_in(root: &Path, hash: u64, outputs: &[String]) -> Result<bool> {
+    let dir: PathBuf = root.join(format!("{:016x}", hash));
+
+    let cached: Vec<PathBuf> = (0..outputs.len())
+        .map(|i: usize| dir.join(i.to_string()))
+        .collect();
+
+    if outputs.is_empty() || !cached.iter().all(|p: &PathBuf| p.is_file()) {
+        return Ok(false);
+    }
+
+    for (from, output) in cached.iter().zip(outputs) {
+        if let Some(parent) = Path::new(output)
+            .parent()
+            .filter(|p: &&Path| !p.as_os_str().is_empty())
+        {
+            create_dir_all(parent).with_context(|| create_err(parent))?;
+        }
+
+        fs::copy(from, output).with_context(|| create_err(output))?;
+    }
+
+    touch_artifact(&dir)?;
+
+    Ok(true)
+}
+
+// Record the time when the artifacts are used to find the least recently used ones
+fn touch_artifact(dir: &Path) -> Result<()> {
+    let path: PathBuf = dir.join("used");
+
+    let mut f: File = File::create(&path).with_context(|| create_err(&path))?;
+    write!(&mut f, "{}", now_secs()).with_context(|| write_err(&path))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, process};
+
+    // Directory for the files of the test, which is empty at first
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir: PathBuf = env::temp_dir().join(format!("attest-{}-{}", name, process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+
+        create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn saved_artifact_is_restored() {
+        let dir: PathBuf = temp_dir("artifact");
+
+        let outputs: Vec<String> = vec![dir.join("out/a.out").display().to_string()];
+
+        create_dir_all(dir.join("out")).unwrap();
+        fs::write(&outputs[0], "binary").unwrap();
+
+        save_artifact_in(&dir.join("artifacts"), 1, &outputs).unwrap();
+
+        fs::remove_dir_all(dir.join("out")).unwrap();
+
+        assert!(restore_artifact_in(&dir.join("artifacts"), 1, &outputs).unwrap());
+        assert_eq!(fs::read_to_string(&outputs[0]).unwrap(), "binary");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_artifact_is_not_restored() {
+        let dir: PathBuf = temp_dir("unknown-artifact");
+
+        let outputs: Vec<String> = vec![dir.join("a.out").display().to_string()];
+
+        assert!(!restore_artifact_in(&dir.join("artifacts"), 1, &outputs).unwrap());
+        assert!(!restore_artifact_in(&dir.join("artifacts"), 1, &[]).unwrap());
+        assert!(!Path::new(&outputs[0]).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn old_artifacts_are_removed() {
+        let dir: PathBuf = temp_dir("old-artifacts");
+
+        let outputs: Vec<String> = vec![dir.join("a.out").display().to_string()];
+
+        fs::write(&outputs[0], "binary").unwrap();
+
+        for hash in 0..ARTIFACT_CACHE_SIZE as u64 + 2 {
+            save_artifact_in(&dir.join("artifacts"), hash, &outputs).unwrap();
+        }
+
+        assert_eq!(
+            fs::read_dir(dir.join("artifacts")).unwrap().count(),
+            ARTIFACT_CACHE_SIZE
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::LazyLock,
+};
+
+use anyhow::{bail, Context, Result};
+
+use regex::Regex;
+
+use serde::Deserialize;
+
+use crate::cache::task_list_cache;
+use crate::config::{Build, Config, FilePath};
+use crate::error::AttestError;
+use crate::task::Task;
+
+// Output of `cargo metadata`
+#[derive(Deserialize, Debug)]
+struct Metadata {
+    packages: Vec<Package>,
+    target_directory: PathBuf,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize, Debug)]
+struct Package {
+    manifest_path: PathBuf,
+    targets: Vec<Target>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Target {
+    name: String,
+    kind: Vec<String>,
+    src_path: PathBuf,
+}
+
+// Whether to derive the commands from the Cargo package
+pub fn is_cargo_project(config: &Config) -> bool {
+    match config.cargo {
+        Some(cargo) => cargo,
+        None => {
+            config.run.is_empty()
+                && matches!(&config.build, Build::Command(c) if c.is_empty())
+                && Path::new("./Cargo.toml").is_file()
+        }
+    }
+}
+
+// Derive the build and run commands for the binary of the task, and track the sources as deps files
+//
+// The binary is the one whose path is the program file, or whose name is the task ID like `abc300_a` or `abc300-a`,
+// or the task letter like `a`.
+// The values set in `attest.toml` are kept
+pub fn apply_cargo(config: &mut Config, task: Option<&Task>) -> Result<()> {
+    apply(config, task, true)
+}
+
+// Same as `apply_cargo`, but keep the commands in `attest.toml` if the program file is not a binary
+pub fn apply_cargo_if_bin(config: &mut Config, task: Option<&Task>) -> Result<()> {
+    apply(config, task, false)
+}
+
+fn apply(config: &mut Config, task: Option<&Task>, required: bool) -> Result<()> {
+    if !is_cargo_project(config) {
+        return Ok(());
+    }
+
+    let metadata: Metadata = cargo_metadata()?;
+
+    let file: Option<PathBuf> = config
+        .file_path()
+        .map(|f: &str| fs::canonicalize(f).unwrap_or_else(|_| PathBuf::from(f)));
+
+    let names: Vec<String> = task.map(bin_names).unwrap_or_default();
+
+    let Some((package, bin)) = find_bin(&metadata.packages, file.as_deref(), &names) else {
+        if !required {
+            return Ok(());
+        }
+
+        bail!(match (config.file_path(), task) {
+            (Some(file), _) => AttestError::Build(format!(
+                r#"no binary in "Cargo.toml" has the path {}"#,
+                file
+            )),
+            (None, Some(_)) => AttestError::Build(format!(
+                r#"no binary in "Cargo.toml" is named {}. Name the binary so, or set the program file by `attest set file <FILE>`"#,
+                names.join(", ")
+            )),
+            (None, None) => AttestError::Usage(String::from(
+                "Please input the URL of the task to find the binary",
+            )),
+        });
+    };
+
+    if config.file_path.is_none() {
+        config.file_path = Some(FilePath::Path(relative(&bin.src_path)));
+    }
+
+    if matches!(&config.build, Build::Command(c) if c.is_empty()) {
+        config.build = Build::Command(
+            ["cargo", "build", "--release", "--bin", &bin.name]
+                .map(String::from)
+                .to_vec(),
+        );
+    }
+
+    if config.run.is_empty() {
+        let binary: String = relative(&metadata.target_directory.join("release").join(&bin.name));
+
+        // A relative path without `./` would be searched in PATH
+        config.run = vec![if Path::new(&binary).is_relative() {
+            format!("./{}", binary)
+        } else {
+            binary
+        }];
+    }
+
+    let mut deps: Vec<PathBuf> = vec![
+        metadata.workspace_root.join("Cargo.toml"),
+        metadata.workspace_root.join("Cargo.lock"),
+        package.manifest_path.clone(),
+    ];
+
+    if let Some(root) = package.manifest_path.parent() {
+        source_files(&root.join("src"), &mut deps);
+    }
+
+    deps.sort();
+
+    deps.dedup();
+
+    for dep in deps.iter().filter(|p: &&PathBuf| p.is_file()) {
+        let dep: String = relative(dep);
+
+        if !config.deps_files.contains(&dep) {
+            config.deps_files.push(dep);
+        }
+    }
+
+    Ok(())
+}
+
+// Names which the binary of the task can have like `abc300_a`, `abc300-a` and `a`, in the order of priority
+//
+// The letter comes from the cached task list, or from the suffix of the task ID
+fn bin_names(task: &Task) -> Vec<String> {
+    let letter: Option<String> = task_list_cache(&task.contest)
+        .and_then(|list: Vec<(String, Task)>| {
+            list.into_iter()
+                .find(|(_, t): &(String, Task)| t.id == task.id)
+        })
+        .map(|(letter, _): (String, Task)| letter.to_lowercase())
+        .or_else(|| {
+            task.id
+                .rsplit_once('_')
+                .map(|(_, letter): (&str, &str)| letter.to_string())
+        });
+
+    let mut names: Vec<String> = vec![task.id.clone(), task.id.replace('_', "-")];
+
+    names.extend(letter);
+
+    names.dedup();
+
+    names
+}
+
+// Find the binary whose path is the program file, or whose name comes first in `names`
+fn find_bin<'a>(
+    packages: &'a [Package],
+    file: Option<&Path>,
+    names: &[String],
+) -> Option<(&'a Package, &'a Target)> {
+    let bins = || {
+        packages
+            .iter()
+            .flat_map(|p: &Package| p.targets.iter().map(move |t: &Target| (p, t)))
+            .filter(|(_, t): &(&Package, &Target)| t.kind.iter().any(|k: &String| k == "bin"))
+    };
+
+    match file {
+        Some(file) => bins().find(|(_, t): &(&Package, &Target)| t.src_path == file),
+        None => names
+            .iter()
+            .find_map(|name: &String| bins().find(|(_, t): &(&Package, &Target)| &t.name == name)),
+    }
+}
+
+// Get the packages and binaries of the workspace
+fn cargo_metadata() -> Result<Metadata> {
+    let output: Output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .output()
+        .with_context(|| AttestError::Build(String::from("cargo couldn't be run")))?;
+
+    if !output.status.success() {
+        bail!(AttestError::Config(format!(
+            "`cargo metadata` failed\n{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )));
+    }
+
+    serde_json::from_slice(&output.stdout).with_context(|| {
+        AttestError::Build(String::from(
+            "the output of `cargo metadata` couldn't be read",
+        ))
+    })
+}
+
+// Line of the progress of cargo like `   Compiling attest v0.1.0`
+static STATUS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:Compiling|Checking|Finished|Running|Fresh|Updating|Locking|Adding|Downloading|Downloaded|Blocking|Building|Removing)\s").unwrap()
+});
+
+// Remove the progress of cargo from its stderr to leave only the warnings
+pub fn strip_status(stderr: &str) -> String {
+    stderr
+        .lines()
+        .filter(|line: &&str| !STATUS.is_match(line))
+        .map(|line: &str| format!("{}\n", line))
+        .collect()
+}
+
+// Collect all files under the directory
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path: PathBuf = entry.path();
+
+        if path.is_dir() {
+            source_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// Path relative to the project root if it is inside of it
+fn relative(path: &Path) -> String {
+    let root: Option<PathBuf> = std::env::current_dir()
+        .ok()
+        .and_then(|d: PathBuf| fs::canonicalize(d).ok());
+
+    root.and_then(|r: PathBuf| path.strip_prefix(r).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(bins: &[(&str, &str)]) -> Package {
+        Package {
+            manifest_path: PathBuf::from("/p/Cargo.toml"),
+            targets: bins
+                .iter()
+                .map(|(name, path): &(&str, &str)| Target {
+                    name: name.to_string(),
+                    kind: vec![String::from("bin")],
+                    src_path: PathBuf::from(path),
+                })
+                .collect(),
+        }
+    }
+
+    fn names(task: &str) -> Vec<String> {
+        bin_names(&Task {
+            contest: String::from("no_such_contest"),
+            id: task.to_string(),
+        })
+    }
+
+    #[test]
+    fn names_of_the_task() {
+        assert_eq!(names("abc300_a"), ["abc300_a", "abc300-a", "a"]);
+    }
+
+    #[test]
+    fn binary_is_found_by_the_letter() {
+        let packages: Vec<Package> = vec![package(&[
+            ("a", "/p/src/bin/a.rs"),
+            ("b", "/p/src/bin/b.rs"),
+        ])];
+
+        let (_, bin): (&Package, &Target) = find_bin(&packages, None, &names("abc300_b")).unwrap();
+
+        assert_eq!(bin.name, "b");
+    }
+
+    #[test]
+    fn task_id_comes_before_the_letter() {
+        let packages: Vec<Package> = vec![package(&[
+            ("a", "/p/src/bin/a.rs"),
+            ("abc300-a", "/p/src/bin/abc300_a.rs"),
+        ])];
+
+        let (_, bin): (&Package, &Target) = find_bin(&packages, None, &names("abc300_a")).unwrap();
+
+        assert_eq!(bin.name, "abc300-a");
+    }
+
+    #[test]
+    fn program_file_comes_before_the_names() {
+        let packages: Vec<Package> = vec![package(&[
+            ("a", "/p/src/bin/a.rs"),
+            ("main", "/p/src/main.rs"),
+        ])];
+
+        let found: Option<(&Package, &Target)> = find_bin(
+            &packages,
+            Some(Path::new("/p/src/main.rs")),
+            &names("abc300_a"),
+        );
+
+        assert_eq!(
+            found.map(|(_, t): (&Package, &Target)| t.name.as_str()),
+            Some("main")
+        );
+
+        assert!(find_bin(
+            &packages,
+            Some(Path::new("/p/other.rs")),
+            &names("abc300_a")
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn libraries_are_not_binaries() {
+        let mut lib: Package = package(&[("a", "/p/src/lib.rs")]);
+
+        lib.targets[0].kind = vec![String::from("lib")];
+
+        assert!(find_bin(&[lib], None, &names("abc300_a")).is_none());
+    }
+}
@@ -0,0 +1,401 @@
+use crate::submit::submit;
+use crate::test::{test, Res};
+use crate::{config, diff_run, error, fetch, show, subcommands, task};
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use clap::{Parser, Subcommand};
+
+/// Tester for AtCoder examples.
+/// This tests your program in the example cases
+#[derive(Parser)]
+enum Arg {
+    /// Test the examples
+    #[clap(visible_alias("t"))]
+    Test {
+        #[arg(help = "URL of AtCoder or task alias like `c`")]
+        url: Option<String>,
+
+        #[clap(short = 'b', long = "build")]
+        build: bool,
+
+        #[clap(short = 'n', long = "num", num_args = 0.., value_delimiter = ' ')]
+        example_num: Vec<usize>,
+
+        #[clap(short = 'p', long = "profile")]
+        profile: Option<String>,
+
+        /// Use only cached examples without connecting to AtCoder
+        #[clap(long = "offline")]
+        offline: bool,
+
+        /// Download the examples again even if they are cached
+        #[clap(long = "refresh", conflicts_with = "offline")]
+        refresh: bool,
+
+        /// Show the warnings of the build
+        #[clap(short = 'w', long = "warnings")]
+        warnings: bool,
+    },
+    /// Submit your code
+    #[clap(visible_alias("s"))]
+    Submit {
+        url: Option<String>,
+
+        #[clap(short = 'l', long = "lang")]
+        lang: Option<String>,
+
+        #[clap(short = 'p', long = "profile")]
+        profile: Option<String>,
+
+        /// Exit without waiting for the verdict
+        #[clap(long = "no-wait")]
+        no_wait: bool,
+    },
+    /// Test and Submit if all tests get AC
+    #[clap(visible_alias("ts"))]
+    Tebmit {
+        url: Option<String>,
+
+        #[clap(short = 'b', long = "build")]
+        build: bool,
+
+        #[clap(short = 'l', long = "lang")]
+        lang: Option<String>,
+
+        #[clap(short = 'p', long = "profile")]
+        profile: Option<String>,
+
+        /// Show the warnings of the build
+        #[clap(short = 'w', long = "warnings")]
+        warnings: bool,
+
+        /// Exit without waiting for the verdict
+        #[clap(long = "no-wait")]
+        no_wait: bool,
+    },
+    /// Run two programs and show the inputs where their outputs differ
+    #[clap(name = "diff-run", visible_alias("dr"))]
+    DiffRun { file_a: String, file_b: String },
+    /// Show or Set the contest to use task aliases like `attest t c`
+    #[clap(visible_alias("c"))]
+    Contest {
+        #[arg(help = "ID or URL of the contest")]
+        contest: Option<String>,
+    },
+    /// Fetch the examples of all tasks in the contest
+    #[clap(visible_alias("f"))]
+    Fetch {
+        #[arg(help = "URL of the contest")]
+        url: String,
+    },
+    /// Manage the cache of tasks
+    Cache {
+        #[command(subcommand)]
+        command: Caches,
+    },
+    /// Show the problem statement
+    Show {
+        #[arg(help = "URL of AtCoder or task alias like `c`")]
+        url: Option<String>,
+
+        /// Show the English statement
+        #[clap(short = 'e', long = "en")]
+        en: bool,
+    },
+    /// Show the metadata of the task
+    Info {
+        #[arg(help = "URL of AtCoder or task alias like `c`")]
+        url: Option<String>,
+    },
+    /// Check attest.toml
+    Config {
+        #[command(subcommand)]
+        command: Configs,
+    },
+    /// Show or Select langs
+    #[clap(visible_alias("l"))]
+    Lang {
+        lang: Option<String>,
+
+        #[clap(short = 'l', long = "list")]
+        list: bool,
+
+        #[clap(short = 'u', long = "url")]
+        url: Option<String>,
+
+        #[clap(short = 's', long = "search")]
+        search: Option<String>,
+
+        /// Use only cached langs without connecting to AtCoder
+        #[clap(long = "offline")]
+        offline: bool,
+
+        /// Write the lang to the global settings shared by all projects
+        #[clap(short = 'g', long = "global")]
+        global: bool,
+    },
+    /// Init the environment to test
+    Init {
+        /// Language to write the settings and the program file for, like rust, cpp or python
+        #[clap(long = "preset")]
+        preset: Option<String>,
+
+        /// Overwrite "attest.toml" if it exists
+        #[clap(short = 'f', long = "force")]
+        force: bool,
+    },
+    /// Set the environment to test
+    Set {
+        #[command(subcommand)]
+        command: Option<Sets>,
+
+        /// Write to the global settings shared by all projects
+        #[clap(short = 'g', long = "global", global = true)]
+        global: bool,
+
+        /// Show the settings with the profile
+        #[clap(short = 'p', long = "profile")]
+        profile: Option<String>,
+    },
+    /// Show or Use profiles
+    Profile {
+        #[command(subcommand)]
+        command: Option<Profiles>,
+    },
+    /// Login to AtCoder
+    Login {
+        #[arg()]
+        user_name: String,
+
+        #[arg()]
+        password: String,
+    },
+    /// Logout from AtCoder
+    Logout,
+}
+
+#[derive(Subcommand)]
+enum Sets {
+    /// Set the build command
+    Build {
+        #[clap(value_delimiter = ' ')]
+        command: Vec<String>,
+    },
+    /// Set the run command
+    Run {
+        #[clap(value_delimiter = ' ')]
+        command: Vec<String>,
+    },
+    #[command(about = r#"Set the test command
+Test command has to be satisfied with below:
+
+You had better use command line arguments.
+
+Output:
+    ```
+    J
+    D
+    ```
+    J: If judge is correct `true` else `false`
+    D (Option): Other discription
+
+Input:
+    ```
+    R
+    C
+    ```
+    R: Result the executing the run command
+    C: Correct answer
+    You can receive input as either stdin or command line arguments"#)]
+    Test {
+        #[clap(value_delimiter = ' ')]
+        command: Vec<String>,
+    },
+    /// Set the program file
+    File { file_path: String },
+    /// Set other files that building depends on
+    Deps {
+        #[clap(short = 'a', long = "add")]
+        add: bool,
+        #[clap(value_delimiter = ' ')]
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Caches {
+    /// Clear the cache of tasks in this directory
+    Clear {
+        /// Clear the cache shared across directories instead
+        #[clap(short = 'g', long = "global")]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum Configs {
+    /// Validate attest.toml and show every problem
+    Check,
+}
+
+#[derive(Subcommand)]
+enum Profiles {
+    /// Use the profile
+    Use { name: String },
+}
+
+// Parse the arguments and run the subcommand, exiting with the code of the error
+pub async fn main() {
+    let args: Arg = Arg::parse();
+
+    if let Err(e) = run(args).await {
+        std::process::exit(error::report(&e));
+    }
+}
+
+// Run the subcommand
+async fn run(args: Arg) -> Result<()> {
+    // `init` creates the project in the current directory
+    let sub_dir: PathBuf = if matches!(args, Arg::Init { .. }) {
+        PathBuf::new()
+    } else {
+        config::enter_project_root()?
+    };
+
+    // Paths given by the user are relative to the directory where attest was run
+    let from_root = |path: String| -> String { sub_dir.join(path).to_string_lossy().into_owned() };
+
+    match args {
+        Arg::Test {
+            url,
+            example_num,
+            build,
+            profile,
+            offline,
+            refresh,
+            warnings,
+        } => {
+            let url: Option<String> = task::resolve_alias(url).await?;
+
+            test(url, example_num, build, profile, offline, refresh, warnings).await?;
+        }
+        Arg::Submit {
+            url,
+            lang,
+            profile,
+            no_wait,
+        } => {
+            let url: Option<String> = task::resolve_alias(url).await?;
+
+            submit(url, lang, profile, !no_wait).await?;
+        }
+        Arg::Tebmit {
+            url,
+            lang,
+            build,
+            profile,
+            warnings,
+            no_wait,
+        } => {
+            let url: Option<String> = task::resolve_alias(url).await?;
+
+            let results: Option<Vec<Option<Res>>> = test(
+                url.clone(),
+                Vec::new(),
+                build,
+                profile.clone(),
+                false,
+                false,
+                warnings,
+            )
+            .await?;
+
+            if let Some(v) = results {
+                if v.iter().all(|&a: &Option<Res>| a == Some(Res::AC)) {
+                    submit(url, lang, profile, !no_wait).await?;
+                }
+            }
+        }
+        Arg::DiffRun { file_a, file_b } => {
+            diff_run::diff_run(from_root(file_a), from_root(file_b)).await?
+        }
+        Arg::Contest { contest } => subcommands::contest(contest).await?,
+        Arg::Fetch { url } => fetch::fetch(url).await?,
+        Arg::Cache { command } => match command {
+            Caches::Clear { global } => subcommands::clear_cache(global)?,
+        },
+        Arg::Show { url, en } => {
+            let url: Option<String> = task::resolve_alias(url).await?;
+
+            show::show(url, en).await?;
+        }
+        Arg::Info { url } => {
+            let url: Option<String> = task::resolve_alias(url).await?;
+
+            subcommands::info(url).await?;
+        }
+        Arg::Config { command } => match command {
+            Configs::Check => config::check_config()?,
+        },
+        Arg::Lang {
+            lang,
+            list,
+            url,
+            search,
+            offline,
+            global,
+        } => subcommands::lang(lang, list, url, search, offline, global).await?,
+        Arg::Init { preset, force } => subcommands::init(preset, force)?,
+        Arg::Set {
+            command,
+            global,
+            profile,
+        } => {
+            // The paths in the global settings are relative to each project root
+            let from_root = |path: String| -> String {
+                if global {
+                    path
+                } else {
+                    from_root(path)
+                }
+            };
+
+            if let Some(command) = command {
+                match command {
+                    Sets::Build { command } => subcommands::set_build(command, global)?,
+                    Sets::Run { command } => subcommands::set_run(command, global)?,
+                    Sets::Test { command } => subcommands::set_test(command, global)?,
+                    Sets::File { file_path } => {
+                        subcommands::set_file(from_root(file_path), global)?
+                    }
+                    Sets::Deps { paths, add } => {
+                        let paths: Vec<String> = paths.into_iter().map(from_root).collect();
+
+                        subcommands::set_deps_file(paths, add, global)?
+                    }
+                }
+            } else {
+                subcommands::show_set(profile.as_deref())?;
+            }
+        }
+        Arg::Profile { command } => {
+            if let Some(command) = command {
+                match command {
+                    Profiles::Use { name } => subcommands::use_profile(name)?,
+                }
+            } else {
+                subcommands::show_profiles()?;
+            }
+        }
+        Arg::Login {
+            user_name,
+            password,
+        } => subcommands::login(user_name, password).await?,
+        Arg::Logout => subcommands::logout()?,
+    }
+
+    Ok(())
+}
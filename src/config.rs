@@ -0,0 +1,928 @@
+use std::collections::BTreeMap;
+use std::env::{current_dir, set_current_dir};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use serde::{Deserialize, Serialize};
+
+use toml::{map::Map, Value};
+
+use crate::error::AttestError;
+use crate::utils::{global_dir, items_toml, Marker};
+
+// Seconds to use the shared cache without downloading again
+pub const DEFAULT_GLOBAL_CACHE_TTL: u64 = 7 * 24 * 60 * 60;
+
+// Seconds to wait for each build step
+pub const DEFAULT_BUILD_TIMEOUT: u64 = 300;
+
+// Keys which can be written in `attest.toml`
+const KNOWN_KEYS: [&str; 13] = [
+    "build",
+    "run",
+    "test",
+    "shell",
+    "file_path",
+    "deps_files",
+    "lang",
+    "global_cache",
+    "global_cache_ttl",
+    "build_timeout",
+    "cargo",
+    "profile",
+    "active_profile",
+];
+
+// Settings in `attest.toml`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    // Command or steps to build the program, or empty not to build
+    pub build: Build,
+    // Command to run the program
+    pub run: Vec<String>,
+    // Command to judge the output instead of comparing with the example
+    pub test: Option<Vec<String>>,
+    // Whether to run the commands through `sh -c`
+    pub shell: bool,
+    pub file_path: Option<FilePath>,
+    // Files to build again when they are changed
+    pub deps_files: Vec<String>,
+    // ID of the language to submit in
+    pub lang: Option<String>,
+    // Whether to share the cache of tasks across directories
+    pub global_cache: bool,
+    pub global_cache_ttl: u64,
+    // Seconds to wait for each build step, or 0 to wait forever
+    pub build_timeout: u64,
+    // Whether to derive the commands from the Cargo package, or None to detect it by `Cargo.toml`
+    pub cargo: Option<bool>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            build: Build::Command(Vec::new()),
+            run: Vec::new(),
+            test: None,
+            shell: false,
+            file_path: None,
+            deps_files: Vec::new(),
+            lang: None,
+            global_cache: false,
+            global_cache_ttl: DEFAULT_GLOBAL_CACHE_TTL,
+            build_timeout: DEFAULT_BUILD_TIMEOUT,
+            cargo: None,
+        }
+    }
+}
+
+// Program file, which can be a path with `{task}` and `{contest}`, or a table from task IDs to paths
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum FilePath {
+    Path(String),
+    Tasks(BTreeMap<String, String>),
+}
+
+// Build command, or steps which run in order like bundling, generating and compiling
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Build {
+    Command(Vec<String>),
+    Steps(Vec<BuildStep>),
+}
+
+// Step of the build, which runs again only when its command or inputs are changed,
+// or some of its outputs don't exist
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuildStep {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub command: Vec<String>,
+    // Files the step reads, which are the program file and `deps_files` if empty
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    // Files the step writes
+    #[serde(default)]
+    pub outputs: Vec<String>,
+}
+
+impl Build {
+    // Commands of all steps
+    pub fn commands_mut(&mut self) -> Vec<&mut Vec<String>> {
+        match self {
+            Build::Command(c) => vec![c],
+            Build::Steps(steps) => steps
+                .iter_mut()
+                .map(|s: &mut BuildStep| &mut s.command)
+                .collect(),
+        }
+    }
+}
+
+impl BuildStep {
+    // Name to show, which is the index of the step if it has no name
+    pub fn label(&self, index: usize) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("build[{}]", index))
+    }
+}
+
+impl Config {
+    // Get the settings from the items of `attest.toml` which are already validated
+    pub fn from_items(items: Map<String, Value>) -> Result<Self> {
+        Value::Table(items)
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                AttestError::Config(format!(
+                    r#""attest.toml" has wrong values: {}"#,
+                    e.message()
+                ))
+                .into()
+            })
+    }
+
+    // Path of the program file after it is resolved for the task
+    pub fn file_path(&self) -> Option<&str> {
+        match &self.file_path {
+            Some(FilePath::Path(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    // Steps to build the program, where the build command is one step
+    pub fn build_steps(&self) -> Vec<BuildStep> {
+        let mut steps: Vec<BuildStep> = match &self.build {
+            Build::Command(c) if c.is_empty() => Vec::new(),
+            // The binary is the run command if it is a path like `./a.out`
+            Build::Command(c) => vec![BuildStep {
+                name: None,
+                command: c.clone(),
+                inputs: Vec::new(),
+                outputs: self
+                    .run
+                    .first()
+                    .filter(|r: &&String| r.contains('/'))
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+            }],
+            Build::Steps(steps) => steps.clone(),
+        };
+
+        let default_inputs: Vec<String> = self
+            .file_path()
+            .map(|s: &str| s.to_string())
+            .into_iter()
+            .chain(self.deps_files.iter().cloned())
+            .collect();
+
+        for step in steps
+            .iter_mut()
+            .filter(|s: &&mut BuildStep| s.inputs.is_empty())
+        {
+            step.inputs = default_inputs.clone();
+        }
+
+        steps
+    }
+
+    // Command to run the program, which has to be set
+    pub fn run_command(&self) -> Result<&[String]> {
+        if self.run.is_empty() {
+            bail!(AttestError::Config(String::from(
+                r#""attest.toml" must have "run" value"#
+            )));
+        }
+
+        Ok(&self.run)
+    }
+}
+
+// Problem of a value in `attest.toml`
+#[derive(Debug, Clone)]
+pub struct Problem {
+    // Path of the key like `profile.release.build[0]`
+    pub key: String,
+    pub message: String,
+    pub suggestion: String,
+    // Unknown keys are only warned since they are ignored
+    pub is_warning: bool,
+}
+
+impl Problem {
+    fn error(key: &str, message: &str, suggestion: &str) -> Self {
+        Problem {
+            key: key.to_string(),
+            message: message.to_string(),
+            suggestion: suggestion.to_string(),
+            is_warning: false,
+        }
+    }
+
+    fn warning(key: &str, message: &str, suggestion: &str) -> Self {
+        Problem {
+            is_warning: true,
+            ..Problem::error(key, message, suggestion)
+        }
+    }
+}
+
+// Where the value of the settings comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Global,
+    Project,
+    Profile(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Global => write!(f, "~/.attest_global/config.toml"),
+            Source::Project => write!(f, "attest.toml"),
+            Source::Profile(name) => write!(f, "profile.{}", name),
+        }
+    }
+}
+
+// Find the directory which has `attest.toml` by searching upward like cargo does
+pub fn project_root() -> Option<PathBuf> {
+    let dir: PathBuf = current_dir().ok()?;
+
+    dir.ancestors()
+        .find(|d: &&Path| d.join("attest.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+// Move to the project root so that the paths in `attest.toml` and `.attest` are resolved against it,
+// and get the directory where attest was run relative to the root
+//
+// Nothing changes if `attest.toml` is not found
+pub fn enter_project_root() -> Result<PathBuf> {
+    let (Some(root), Ok(dir)) = (project_root(), current_dir()) else {
+        return Ok(PathBuf::new());
+    };
+
+    set_current_dir(&root)
+        .with_context(|| AttestError::Io(format!("{} couldn't be entered", root.display())))?;
+
+    Ok(dir.strip_prefix(&root).unwrap_or(&dir).to_path_buf())
+}
+
+// Path of the settings shared by all projects
+pub fn global_config_path() -> Result<PathBuf> {
+    Ok(global_dir()?.join("config.toml"))
+}
+
+// Items of the global settings and `attest.toml`
+struct Layers {
+    global: Map<String, Value>,
+    project: Map<String, Value>,
+    problems: Vec<Problem>,
+}
+
+// Values of the settings with where each value comes from
+pub type LayeredItems = BTreeMap<String, (Value, Source)>;
+
+// Read the global settings and `attest.toml` with the problems of their values
+fn read_layers() -> Result<Layers> {
+    if !Path::new("./attest.toml").is_file() {
+        bail!(AttestError::Config(String::from(
+            r#""attest.toml" was not found in the current directory or its parents"#
+        )));
+    }
+
+    let global_path: PathBuf = global_config_path()?;
+
+    let global: Map<String, Value> = if global_path.is_file() {
+        items_toml(&global_path)?
+    } else {
+        Map::new()
+    };
+
+    let project: Map<String, Value> = items_toml("./attest.toml")?;
+
+    let mut problems: Vec<Problem> = validate_global(&global);
+
+    problems.append(&mut validate(&project));
+
+    Ok(Layers {
+        global,
+        project,
+        problems,
+    })
+}
+
+// Get the items of the global settings and `attest.toml` with only warnings left in the problems
+fn load_items() -> Result<Layers> {
+    let mut layers: Layers = read_layers()?;
+
+    let errors: Vec<String> = layers
+        .problems
+        .iter()
+        .filter(|p: &&Problem| !p.is_warning)
+        .map(|p: &Problem| format!("{}: {}", p.key, p.message))
+        .collect();
+
+    if !errors.is_empty() {
+        bail!(AttestError::Config(format!(
+            r#"The settings have wrong values. Run `attest config check` to see suggestions
+    {}"#,
+            errors.join("\n    ")
+        )));
+    }
+
+    layers.problems.retain(|p: &Problem| p.is_warning);
+
+    Ok(layers)
+}
+
+// Show the warnings of the settings
+pub fn report_warnings(warnings: &[Problem]) {
+    for p in warnings {
+        eprintln!(
+            "{} {}: {} ({})",
+            Marker::Minus,
+            p.key,
+            p.message,
+            p.suggestion
+        );
+    }
+}
+
+// Get the values of the global settings, `attest.toml` and the profile layered in this order
+// with where each value comes from, and the warnings of the settings
+pub fn layered_items(profile: Option<&str>) -> Result<(LayeredItems, Vec<Problem>)> {
+    let Layers {
+        global,
+        project,
+        problems: warnings,
+    } = load_items()?;
+
+    Ok((layer(global, project, profile)?, warnings))
+}
+
+// Layer the items of the global settings, `attest.toml` and the profile
+//
+// The profile is the active one of `attest.toml` if it is not given
+fn layer(
+    global: Map<String, Value>,
+    mut project: Map<String, Value>,
+    profile: Option<&str>,
+) -> Result<LayeredItems> {
+    let mut items: LayeredItems = BTreeMap::new();
+
+    for (key, value) in global {
+        if !matches!(key.as_str(), "profile" | "active_profile") {
+            items.insert(key, (value, Source::Global));
+        }
+    }
+
+    let profiles: Option<Value> = project.remove("profile");
+
+    let active: Option<Value> = project.remove("active_profile");
+
+    for (key, value) in project {
+        items.insert(key, (value, Source::Project));
+    }
+
+    if let Some(name) = profile.or(active.as_ref().and_then(|v: &Value| v.as_str())) {
+        let profile_items: &Map<String, Value> = profiles
+            .as_ref()
+            .and_then(|v: &Value| v.get(name))
+            .and_then(|v: &Value| v.as_table())
+            .ok_or_else(|| {
+                AttestError::Config(format!(r#"the profile "{}" does not exist"#, name))
+            })?;
+
+        for (key, value) in profile_items {
+            items.insert(
+                key.clone(),
+                (value.clone(), Source::Profile(name.to_string())),
+            );
+        }
+    }
+
+    Ok(items)
+}
+
+// Get settings with the global settings, `attest.toml` and the profile layered,
+// and the warnings of the settings
+pub fn settings(profile: Option<&str>) -> Result<(Config, Vec<Problem>)> {
+    let (items, warnings): (LayeredItems, Vec<Problem>) = layered_items(profile)?;
+
+    let config: Config = Config::from_items(
+        items
+            .into_iter()
+            .map(|(key, (value, _)): (String, (Value, Source))| (key, value))
+            .collect(),
+    )?;
+
+    Ok((config, warnings))
+}
+
+// Validate the global settings and `attest.toml`, and show every problem with a suggestion
+pub fn check_config() -> Result<()> {
+    let Layers {
+        global,
+        project: items,
+        mut problems,
+    } = read_layers()?;
+
+    let has_run: bool = global.contains_key("run")
+        || items.contains_key("run")
+        || items
+            .get("profile")
+            .and_then(|v: &Value| v.as_table())
+            .is_some_and(|t: &Map<String, Value>| {
+                t.values().any(|p: &Value| p.get("run").is_some())
+            });
+
+    // The run command of a Cargo package is derived from it
+    if !has_run && !Path::new("./Cargo.toml").is_file() {
+        problems.push(Problem::warning(
+            "run",
+            "the value is not set, so `attest test` cannot run the program",
+            r#"set it like run = ["./a.out"] or by `attest set run <COMMAND>`"#,
+        ));
+    }
+
+    for p in &problems {
+        let marker: Marker = if p.is_warning {
+            Marker::X
+        } else {
+            Marker::Minus
+        };
+
+        println!("{} {}: {}", marker, p.key, p.message);
+        println!("    {}", p.suggestion);
+    }
+
+    let error_count: usize = problems.iter().filter(|p: &&Problem| !p.is_warning).count();
+
+    if error_count > 0 {
+        bail!(AttestError::Config(format!(
+            r#""attest.toml" has {} wrong values"#,
+            error_count
+        )));
+    }
+
+    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
+}
+
+// Find all problems of the items of the global settings
+//
+// The keys are prefixed with the path of the file to tell from problems of `attest.toml`
+pub fn validate_global(items: &Map<String, Value>) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = Vec::new();
+
+    let shared: Map<String, Value> = items
+        .iter()
+        .filter(|(key, _): &(&String, &Value)| {
+            !matches!(key.as_str(), "profile" | "active_profile")
+        })
+        .map(|(key, value): (&String, &Value)| (key.clone(), value.clone()))
+        .collect();
+
+    validate_items(&shared, "", &mut problems);
+
+    for key in ["profile", "active_profile"] {
+        if items.contains_key(key) {
+            problems.push(Problem::warning(
+                key,
+                "profiles are read only from attest.toml",
+                "move it to attest.toml",
+            ));
+        }
+    }
+
+    for p in problems.iter_mut() {
+        p.key = format!("{}: {}", Source::Global, p.key);
+    }
+
+    problems
+}
+
+// Find all problems of the items of `attest.toml`
+pub fn validate(items: &Map<String, Value>) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = Vec::new();
+
+    validate_items(items, "", &mut problems);
+
+    if let Some(profiles) = items.get("profile") {
+        match profiles.as_table() {
+            Some(profiles) => {
+                for (name, profile) in profiles {
+                    let key: String = format!("profile.{}", name);
+
+                    match profile.as_table() {
+                        Some(t) => validate_items(t, &format!("{}.", key), &mut problems),
+                        None => problems.push(Problem::error(
+                            &key,
+                            "the profile has to be table",
+                            &format!("write it as [{}]", key),
+                        )),
+                    }
+                }
+            }
+            None => problems.push(Problem::error(
+                "profile",
+                "the value has to be table of profiles",
+                "write profiles as [profile.<NAME>]",
+            )),
+        }
+    }
+
+    if let Some(active) = items.get("active_profile") {
+        match active.as_str() {
+            Some(name)
+                if items
+                    .get("profile")
+                    .and_then(|p: &Value| p.get(name))
+                    .is_none() =>
+            {
+                problems.push(Problem::error(
+                    "active_profile",
+                    &format!(r#"the profile "{}" does not exist"#, name),
+                    &format!("add [profile.{}] or run `attest profile use <NAME>`", name),
+                ))
+            }
+            Some(_) => {}
+            None => problems.push(Problem::error(
+                "active_profile",
+                "the value has to be string",
+                "run `attest profile use <NAME>`",
+            )),
+        }
+    }
+
+    problems
+}
+
+// Find problems of the items at the top level or in a profile
+fn validate_items(items: &Map<String, Value>, prefix: &str, problems: &mut Vec<Problem>) {
+    for (key, value) in items {
+        let path: String = format!("{}{}", prefix, key);
+
+        match key.as_str() {
+            "build" if value.is_array() && value.get(0).is_some_and(Value::is_table) => {
+                validate_build_steps(value, &path, problems)
+            }
+            "build" | "run" | "test" | "deps_files" => validate_string_list(value, &path, problems),
+            "file_path" => match value {
+                Value::String(_) => {}
+                Value::Table(t) => {
+                    for (task, v) in t {
+                        if !v.is_str() {
+                            problems.push(Problem::error(
+                                &format!("{}.{}", path, task),
+                                "the value has to be string",
+                                &format!(r#"write it like {} = "main.rs""#, task),
+                            ));
+                        }
+                    }
+                }
+                _ => problems.push(Problem::error(
+                    &path,
+                    "the value has to be string or table",
+                    r#"write it like file_path = "main.rs""#,
+                )),
+            },
+            "lang" if !value.is_str() => problems.push(Problem::error(
+                &path,
+                "the value has to be string",
+                "set it by `attest lang <LANG>`",
+            )),
+            "global_cache" | "shell" | "cargo" if !value.is_bool() => {
+                problems.push(Problem::error(
+                    &path,
+                    "the value has to be boolean",
+                    &format!("write it like {} = true", key),
+                ))
+            }
+            "global_cache_ttl" | "build_timeout"
+                if value.as_integer().is_none_or(|v: i64| v < 0) =>
+            {
+                problems.push(Problem::error(
+                    &path,
+                    "the value has to be non-negative integer",
+                    &format!(
+                        "write seconds like {} = {}",
+                        key,
+                        match key.as_str() {
+                            "build_timeout" => DEFAULT_BUILD_TIMEOUT,
+                            _ => DEFAULT_GLOBAL_CACHE_TTL,
+                        }
+                    ),
+                ))
+            }
+            "profile" | "active_profile" if prefix.is_empty() => {}
+            "profile" | "active_profile" => problems.push(Problem::warning(
+                &path,
+                "profiles cannot be nested",
+                "write profiles at the top level of attest.toml",
+            )),
+            k if KNOWN_KEYS.contains(&k) => {}
+            k => {
+                let suggestion: String = match similar_key(k) {
+                    Some(s) => format!(r#"did you mean "{}"?"#, s),
+                    None => String::from("remove it since it is ignored"),
+                };
+
+                problems.push(Problem::warning(&path, "unknown key", &suggestion))
+            }
+        }
+    }
+}
+
+// Find problems of the steps of the build
+fn validate_build_steps(value: &Value, path: &str, problems: &mut Vec<Problem>) {
+    for (i, step) in value.as_array().unwrap().iter().enumerate() {
+        let step_path: String = format!("{}[{}]", path, i);
+
+        let Some(step) = step.as_table() else {
+            problems.push(Problem::error(
+                &step_path,
+                "the step has to be table",
+                r#"write it like { name = "compile", command = ["g++", "main.cpp"] }"#,
+            ));
+            continue;
+        };
+
+        if !step.contains_key("command") {
+            problems.push(Problem::error(
+                &step_path,
+                r#"the step must have "command" value"#,
+                r#"write it like command = ["g++", "main.cpp"]"#,
+            ));
+        }
+
+        for (key, v) in step {
+            let key_path: String = format!("{}.{}", step_path, key);
+
+            match key.as_str() {
+                "command" | "inputs" | "outputs" => validate_string_list(v, &key_path, problems),
+                "name" if !v.is_str() => problems.push(Problem::error(
+                    &key_path,
+                    "the value has to be string",
+                    r#"write it like name = "compile""#,
+                )),
+                "name" => {}
+                _ => problems.push(Problem::warning(
+                    &key_path,
+                    "unknown key",
+                    "the keys of a step are name, command, inputs and outputs",
+                )),
+            }
+        }
+    }
+}
+
+// Find problems of the value which has to be array of strings
+fn validate_string_list(value: &Value, path: &str, problems: &mut Vec<Problem>) {
+    let Some(list) = value.as_array() else {
+        problems.push(Problem::error(
+            path,
+            "the value has to be array of strings",
+            &format!(
+                r#"write it like {} = ["g++", "main.cpp"]"#,
+                path.rsplit('.').next().unwrap()
+            ),
+        ));
+        return;
+    };
+
+    for (i, v) in list.iter().enumerate() {
+        if !v.is_str() {
+            problems.push(Problem::error(
+                &format!("{}[{}]", path, i),
+                "the item has to be string",
+                &format!("quote it like \"{}\"", v),
+            ));
+        }
+    }
+}
+
+// Find the known key which is close to the unknown key like `biuld`
+fn similar_key(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|k: &&str| (edit_distance(key, k), *k))
+        .filter(|(d, _): &(usize, &str)| *d <= 2)
+        .min()
+        .map(|(_, k): (usize, &str)| k)
+}
+
+// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+
+    let mut dp: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev: usize = dp[0];
+        dp[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cur: usize = dp[j + 1];
+            dp[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(dp[j]).min(dp[j + 1]) + 1
+            };
+            prev = cur;
+        }
+    }
+
+    dp[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(toml: &str) -> Vec<Problem> {
+        validate(&toml.parse::<Map<String, Value>>().unwrap())
+    }
+
+    fn keys(problems: &[Problem]) -> Vec<&str> {
+        problems.iter().map(|p: &Problem| p.key.as_str()).collect()
+    }
+
+    #[test]
+    fn valid_settings_have_no_problems() {
+        let problems: Vec<Problem> = problems(
+            r#"
+            build = ["g++", "main.cpp"]
+            run = ["./a.out"]
+            file_path = { abc300_a = "a.cpp" }
+            active_profile = "release"
+
+            [profile.release]
+            build = [{ name = "compile", command = ["g++", "-O2", "main.cpp"] }]
+            "#,
+        );
+
+        assert!(problems.is_empty(), "{:?}", problems);
+    }
+
+    #[test]
+    fn wrong_types_are_errors() {
+        let problems: Vec<Problem> = problems(
+            r#"
+            build = "g++ main.cpp"
+            run = ["./a.out", 1]
+            shell = "yes"
+            build_timeout = -1
+            "#,
+        );
+
+        assert_eq!(
+            keys(&problems),
+            ["build", "build_timeout", "run[1]", "shell"]
+        );
+        assert!(problems.iter().all(|p: &Problem| !p.is_warning));
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings_with_similar_keys() {
+        let problems: Vec<Problem> = problems(
+            r#"
+            biuld = ["g++", "main.cpp"]
+            colour = true
+            "#,
+        );
+
+        assert_eq!(keys(&problems), ["biuld", "colour"]);
+        assert!(problems.iter().all(|p: &Problem| p.is_warning));
+        assert_eq!(problems[0].suggestion, r#"did you mean "build"?"#);
+        assert_eq!(problems[1].suggestion, "remove it since it is ignored");
+    }
+
+    #[test]
+    fn problems_in_profiles_have_their_paths() {
+        let problems: Vec<Problem> = problems(
+            r#"
+            active_profile = "debug"
+
+            [profile.release]
+            build = [{ name = 1 }]
+            "#,
+        );
+
+        assert_eq!(
+            keys(&problems),
+            [
+                "profile.release.build[0]",
+                "profile.release.build[0].name",
+                "active_profile"
+            ]
+        );
+    }
+
+    #[test]
+    fn profiles_in_global_settings_are_warned() {
+        let problems: Vec<Problem> = validate_global(
+            &r#"
+            lang = "C++"
+            active_profile = "release"
+            "#
+            .parse::<Map<String, Value>>()
+            .unwrap(),
+        );
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].is_warning);
+        assert!(problems[0].key.ends_with("active_profile"));
+    }
+
+    fn items(toml: &str) -> Map<String, Value> {
+        toml.parse::<Map<String, Value>>().unwrap()
+    }
+
+    fn layered(profile: Option<&str>) -> Result<LayeredItems> {
+        layer(
+            items(
+                r#"
+                lang = "C++"
+                build = ["g++", "main.cpp"]
+                active_profile = "ignored"
+                "#,
+            ),
+            items(
+                r#"
+                build = ["g++", "-O2", "main.cpp"]
+                run = ["./a.out"]
+                active_profile = "debug"
+
+                [profile.debug]
+                build = ["g++", "-g", "main.cpp"]
+
+                [profile.release]
+                run = ["./release"]
+                "#,
+            ),
+            profile,
+        )
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let items: LayeredItems = layered(Some("release")).unwrap();
+
+        assert_eq!(items["lang"], (Value::from("C++"), Source::Global));
+        assert_eq!(
+            items["build"],
+            (Value::from(vec!["g++", "-O2", "main.cpp"]), Source::Project)
+        );
+        assert_eq!(
+            items["run"],
+            (
+                Value::from(vec!["./release"]),
+                Source::Profile(String::from("release"))
+            )
+        );
+    }
+
+    #[test]
+    fn active_profile_is_layered_without_profile() {
+        let items: LayeredItems = layered(None).unwrap();
+
+        assert_eq!(
+            items["build"],
+            (
+                Value::from(vec!["g++", "-g", "main.cpp"]),
+                Source::Profile(String::from("debug"))
+            )
+        );
+        assert!(!items.contains_key("profile"));
+        assert!(!items.contains_key("active_profile"));
+    }
+
+    #[test]
+    fn missing_profile_is_error() {
+        assert!(layered(Some("fast")).is_err());
+    }
+
+    #[test]
+    fn similar_keys_are_within_two_edits() {
+        assert_eq!(similar_key("biuld"), Some("build"));
+        assert_eq!(similar_key("rnu"), Some("run"));
+        assert_eq!(similar_key("file"), None);
+        assert_eq!(similar_key("completely_unknown"), None);
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("build", "build"), 0);
+        assert_eq!(edit_distance("biuld", "build"), 2);
+        assert_eq!(edit_distance("", "run"), 3);
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    env::current_dir,
+    fmt::Write as _,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process::Output,
+    time::Duration,
+};
+
+use crate::cache::{current_task, task_cache, TaskCache};
+use crate::cargo::apply_cargo_if_bin;
+use crate::config::{report_warnings, settings, Config, FilePath, Problem};
+use crate::task::{expand_commands, resolve_file_path, Task};
+use crate::test::{build, judge, render_ce, spawn_command, BUILD_STEPS_CACHE, IO};
+use crate::utils::{create_err, file_read_to_string, link_from_copy, Marker};
+
+use anyhow::{Context, Result};
+
+use tokio::time;
+
+// Result of running a program for one input
+enum Run {
+    Exited(Output),
+    #[allow(clippy::upper_case_acronyms)]
+    TLE,
+}
+
+impl Run {
+    fn stdout(&self) -> &str {
+        match self {
+            Run::Exited(output) => std::str::from_utf8(&output.stdout).unwrap_or(""),
+            Run::TLE => "",
+        }
+    }
+
+    fn status(&self) -> &str {
+        match self {
+            Run::Exited(output) if output.status.code() == Some(0) => "exited successfully",
+            Run::Exited(_) => "\x1b[33mRE\x1b[m",
+            Run::TLE => "\x1b[33mTLE\x1b[m",
+        }
+    }
+}
+
+// Run two programs and show the inputs where outputs differ
+pub async fn diff_run(file_a: String, file_b: String) -> Result<()> {
+    let (mut config, warnings): (Config, Vec<Problem>) = settings(None)?;
+
+    report_warnings(&warnings);
+
+    resolve_file_path(&mut config, &link_from_copy())?;
+
+    let dir: PathBuf = current_dir()?;
+
+    let (examples, time_limit, tolerance): (Vec<IO>, u128, Option<f64>) = cached_examples();
+
+    let mut inputs: Vec<(String, String)> = examples
+        .into_iter()
+        .enumerate()
+        .map(|(i, io): (usize, IO)| (format!("example{}", i + 1), io.input))
+        .collect();
+
+    inputs.append(&mut local_cases()?);
+
+    if inputs.is_empty() {
+        println!("{}", Marker::minus("There are no inputs to run"));
+        return Ok(());
+    }
+
+    let runs_a: Option<Vec<Run>> = run_all(&config, &file_a, &dir, &inputs, time_limit).await?;
+
+    let runs_b: Option<Vec<Run>> = run_all(&config, &file_b, &dir, &inputs, time_limit).await?;
+
+    // The binary now belongs to one of the given files, so the next test has to rebuild
+    File::create(BUILD_STEPS_CACHE).with_context(|| create_err(BUILD_STEPS_CACHE))?;
+
+    let (Some(runs_a), Some(runs_b)) = (runs_a, runs_b) else {
+        return Ok(());
+    };
+
+    let test_command: Option<Vec<String>> = config.test.clone();
+
+    let mut diff_count: usize = 0;
+
+    for ((name, input), (a, b)) in inputs.iter().zip(runs_a.iter().zip(runs_b.iter())) {
+        let io: IO = IO::new(input.clone(), a.stdout().to_string());
+
+        let is_same: bool = match (a, b) {
+            (Run::Exited(x), Run::Exited(y)) if x.status.success() && y.status.success() => {
+                judge(b.stdout(), &io, &test_command, tolerance, &dir)
+                    .await?
+                    .0
+            }
+            _ => a.status() == b.status(),
+        };
+
+        if is_same {
+            continue;
+        }
+
+        diff_count += 1;
+
+        let mut buf: String = String::new();
+
+        writeln!(buf, "{} \x1b[35m{}\x1b[m", Marker::X, name)?;
+        writeln!(buf, "{} \x1b[33mDIFF\x1b[m\n", Marker::Minus)?;
+        writeln!(buf, "{} input:\n{}", Marker::X, input)?;
+        writeln!(
+            buf,
+            "{} {} ({}):\n{}",
+            Marker::X,
+            file_a,
+            a.status(),
+            a.stdout()
+        )?;
+        writeln!(
+            buf,
+            "{} {} ({}):\n{}",
+            Marker::X,
+            file_b,
+            b.status(),
+            b.stdout()
+        )?;
+
+        println!("{}", buf);
+    }
+
+    if diff_count == 0 {
+        println!(
+            "{}",
+            Marker::plus(format!(
+                "\x1b[32mSame outputs\x1b[m for {} inputs",
+                inputs.len()
+            ))
+        );
+    } else {
+        println!(
+            "{}",
+            Marker::minus(format!(
+                "\x1b[33mDifferent outputs\x1b[m for {} of {} inputs",
+                diff_count,
+                inputs.len()
+            ))
+        );
+    }
+
+    Ok(())
+}
+
+// Get examples, time limit and tolerance of the current task, or nothing if no task has been tested yet
+fn cached_examples() -> (Vec<IO>, u128, Option<f64>) {
+    current_task()
+        .and_then(|task: Task| task_cache(&task))
+        .map_or((Vec::new(), u128::MAX, None), |c: TaskCache| {
+            (c.examples, c.time_limit, c.meta.tolerance)
+        })
+}
+
+// Get inputs written by the user in `.attest/cases`
+fn local_cases() -> Result<Vec<(String, String)>> {
+    let Ok(entries) = fs::read_dir("./.attest/cases") else {
+        return Ok(Vec::new());
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p: &PathBuf| p.is_file())
+        .collect();
+
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|p: PathBuf| {
+            let name: String = p.file_name().unwrap().to_string_lossy().into_owned();
+            Ok((name, file_read_to_string(&p)?))
+        })
+        .collect()
+}
+
+// Replace the program file in the settings with the given file
+//
+// Only the arguments equal to the program file are replaced, and `{file}` is expanded later
+fn with_file(config: &Config, file: &str) -> Config {
+    let mut config: Config = config.clone();
+
+    if let Some(before) = config.file_path().map(|s: &str| s.to_string()) {
+        for c in config
+            .build
+            .commands_mut()
+            .into_iter()
+            .flatten()
+            .chain(config.run.iter_mut())
+        {
+            if *c == before {
+                *c = file.to_string();
+            }
+        }
+    }
+
+    config.file_path = Some(FilePath::Path(file.to_string()));
+
+    config
+}
+
+// Build the file and run it for all inputs
+async fn run_all<T: AsRef<Path>>(
+    config: &Config,
+    file: &str,
+    dir: T,
+    inputs: &[(String, String)],
+    time_limit: u128,
+) -> Result<Option<Vec<Run>>> {
+    let mut config: Config = with_file(config, file);
+
+    apply_cargo_if_bin(&mut config, current_task().as_ref())?;
+
+    expand_commands(&mut config, current_task().as_ref())?;
+
+    if let Some(built) = build(&config, &dir, true).await?.last {
+        if !built.success() {
+            println!("{}", render_ce(&built, Some(file)));
+            return Ok(None);
+        }
+    }
+
+    let (execute_command, args): (&String, &[String]) =
+        config.run_command()?.split_first().unwrap();
+
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (_, input) in inputs {
+        let output = spawn_command(input, &dir, execute_command, args).await?;
+
+        runs.push(
+            match time::timeout(Duration::from_millis(time_limit as u64), output).await {
+                Ok(v) => Run::Exited(v?),
+                Err(_) => Run::TLE,
+            },
+        );
+    }
+
+    Ok(Some(runs))
+}
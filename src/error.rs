@@ -0,0 +1,96 @@
+use std::fmt;
+
+use anyhow::Error;
+
+use crate::utils::Marker;
+
+// Errors shown to users with hints
+#[derive(Debug)]
+pub enum AttestError {
+    // `attest.toml` is missing or has wrong values
+    Config(String),
+    // Arguments are wrong or not enough
+    Usage(String),
+    // Failed to connect to AtCoder
+    Network(String),
+    // The page of AtCoder doesn't have what is expected
+    Scrape(String),
+    // Failed to build or run the program
+    Build(String),
+    // Not logged in or failed to login
+    Auth(String),
+    // Failed to read or write files
+    Io(String),
+}
+
+impl AttestError {
+    // Hint to fix the error
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AttestError::Config(_) => {
+                Some("Check \"attest.toml\", or run `attest init` to create it")
+            }
+            AttestError::Usage(_) => Some("Run `attest help` to see the usage"),
+            AttestError::Network(_) => {
+                Some("Check the network connection, or use `--offline` if the task is cached")
+            }
+            AttestError::Scrape(_) => {
+                Some("The contest may not start, or you may not have permission to see the page")
+            }
+            AttestError::Build(_) => Some("Check the commands by `attest set`"),
+            AttestError::Auth(_) => Some("Run `attest login` to login to AtCoder"),
+            AttestError::Io(_) => {
+                Some("Check that the path exists and you have permission to access it")
+            }
+        }
+    }
+
+    // Exit code of the process
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AttestError::Usage(_) => 2,
+            AttestError::Config(_) => 3,
+            AttestError::Network(_) => 4,
+            AttestError::Scrape(_) => 5,
+            AttestError::Build(_) => 6,
+            AttestError::Auth(_) => 7,
+            AttestError::Io(_) => 8,
+        }
+    }
+}
+
+impl fmt::Display for AttestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttestError::Config(s)
+            | AttestError::Usage(s)
+            | AttestError::Network(s)
+            | AttestError::Scrape(s)
+            | AttestError::Build(s)
+            | AttestError::Auth(s)
+            | AttestError::Io(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl std::error::Error for AttestError {}
+
+// Print the error with its causes and hint, and get the exit code
+pub fn report(err: &Error) -> i32 {
+    eprintln!("{}", Marker::minus(err));
+
+    for cause in err.chain().skip(1) {
+        eprintln!("    caused by: {}", cause);
+    }
+
+    let attest_error: Option<&AttestError> = err.downcast_ref().or_else(|| {
+        err.chain()
+            .find_map(|e: &(dyn std::error::Error + 'static)| e.downcast_ref())
+    });
+
+    if let Some(hint) = attest_error.and_then(|e: &AttestError| e.hint()) {
+        eprintln!("{} hint: {}", Marker::X, hint);
+    }
+
+    attest_error.map_or(1, |e: &AttestError| e.exit_code())
+}
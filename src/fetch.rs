@@ -0,0 +1,66 @@
+use anyhow::{bail, Error, Result};
+
+use reqwest::Client;
+
+use crate::cache::{save_task_cache, TaskCache};
+use crate::error::AttestError;
+use crate::problem::download_task;
+use crate::task::{contest_from_url, task_list, Task};
+use crate::utils::{make_client, Marker};
+
+// Fetch examples and time limits of all tasks in the contest
+pub async fn fetch(url: String) -> Result<()> {
+    let contest: String = contest_from_url(&url)
+        .ok_or_else(|| AttestError::Usage(String::from("Please input the URL of the contest")))?;
+
+    let client: Client = make_client();
+
+    let tasks: Vec<(String, Task)> = task_list(&contest).await?;
+
+    if tasks.is_empty() {
+        bail!(AttestError::Scrape(format!(
+            "no tasks were found in {}",
+            contest
+        )));
+    }
+
+    let total: usize = tasks.len();
+
+    let mut failed: usize = 0;
+
+    // The error of the first failed task decides the exit code
+    let mut first_err: Option<Error> = None;
+
+    for (letter, task) in tasks {
+        let cache: TaskCache = match download_task(&client, &task).await {
+            Ok(cache) => cache,
+            Err(e) => {
+                println!("{} {} {}: {}", Marker::Minus, letter, task.id, e);
+                failed += 1;
+                first_err.get_or_insert(e);
+                continue;
+            }
+        };
+
+        save_task_cache(&task, &cache)?;
+
+        println!(
+            "{} {} {}: {} examples",
+            Marker::Plus,
+            letter,
+            task.id,
+            cache.examples.len()
+        );
+    }
+
+    if let Some(e) = first_err {
+        return Err(e.context(format!(
+            "{} of {} tasks couldn't be downloaded",
+            failed, total
+        )));
+    }
+
+    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
+}
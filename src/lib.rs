@@ -0,0 +1,29 @@
+// Tester for AtCoder examples
+//
+// The `attest` and `att` binaries are thin clients of this library
+
+mod cache;
+mod cargo;
+mod cli;
+mod config;
+mod diff_run;
+mod error;
+mod fetch;
+mod preset;
+mod problem;
+mod show;
+mod subcommands;
+mod submit;
+mod task;
+mod test;
+mod utils;
+
+pub use error::AttestError;
+pub use problem::{get_time_limit, select_samples, ProblemMeta, SampleError};
+pub use submit::{submit_code, wait_verdict, Lang, Verdict};
+pub use test::{check, tester, ExampleResult, Res, TestConfig, IO};
+pub use utils::{client_with_cookies, make_client};
+
+// Entry point of the binaries, which is not a part of the library API
+#[doc(hidden)]
+pub use cli::main as cli_main;
@@ -0,0 +1,165 @@
+// Settings of a language to start a project with
+#[derive(Debug, Clone, Copy)]
+pub struct Preset {
+    pub name: &'static str,
+    // Name of the language on AtCoder
+    pub lang_name: &'static str,
+    // ID of the language in the submit form
+    pub lang: &'static str,
+    pub build: &'static [&'static str],
+    pub run: &'static [&'static str],
+    pub file_path: &'static str,
+    // Code to write in the program file
+    pub template: &'static str,
+}
+
+// Presets with the compile flags of AtCoder
+pub const PRESETS: [Preset; 6] = [
+    Preset {
+        name: "cpp",
+        lang_name: "C++ 20 (gcc 12.2)",
+        lang: "5001",
+        build: &[
+            "g++",
+            "-std=gnu++20",
+            "-O2",
+            "-DONLINE_JUDGE",
+            "-DATCODER",
+            "-Wall",
+            "-Wextra",
+            "-o",
+            "{tmp}/a.out",
+            "{file}",
+        ],
+        run: &["{tmp}/a.out"],
+        file_path: "main.cpp",
+        template: r#"#include <bits/stdc++.h>
+using namespace std;
+
+int main() {
+    cin.tie(nullptr);
+    ios::sync_with_stdio(false);
+
+}
+"#,
+    },
+    Preset {
+        name: "rust",
+        lang_name: "Rust (rustc 1.70.0)",
+        lang: "5054",
+        build: &[
+            "rustc",
+            "--edition=2021",
+            "-C",
+            "opt-level=3",
+            "-o",
+            "{tmp}/main",
+            "{file}",
+        ],
+        run: &["{tmp}/main"],
+        file_path: "main.rs",
+        template: r#"use std::io::{self, Read};
+
+fn main() {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap();
+    let mut it = input.split_ascii_whitespace();
+
+}
+"#,
+    },
+    Preset {
+        name: "python",
+        lang_name: "Python (CPython 3.11.4)",
+        lang: "5055",
+        build: &[],
+        run: &["python3", "{file}"],
+        file_path: "main.py",
+        template: r#"import sys
+
+input = sys.stdin.readline
+
+
+def main():
+    pass
+
+
+main()
+"#,
+    },
+    Preset {
+        name: "pypy",
+        lang_name: "Python (PyPy 3.10-v7.3.12)",
+        lang: "5078",
+        build: &[],
+        run: &["pypy3", "{file}"],
+        file_path: "main.py",
+        template: r#"import sys
+
+input = sys.stdin.readline
+
+
+def main():
+    pass
+
+
+main()
+"#,
+    },
+    Preset {
+        name: "java",
+        lang_name: "Java (OpenJDK 17)",
+        lang: "5005",
+        build: &["javac", "-d", "{tmp}", "{file}"],
+        run: &[
+            "java",
+            "-Xss1024M",
+            "-DONLINE_JUDGE=true",
+            "-cp",
+            "{tmp}",
+            "Main",
+        ],
+        file_path: "Main.java",
+        template: r#"import java.util.*;
+
+public class Main {
+    public static void main(String[] args) {
+        Scanner sc = new Scanner(System.in);
+
+    }
+}
+"#,
+    },
+    Preset {
+        name: "go",
+        lang_name: "Go (go 1.20.6)",
+        lang: "5002",
+        build: &["go", "build", "-o", "{tmp}/a.out", "{file}"],
+        run: &["{tmp}/a.out"],
+        file_path: "main.go",
+        template: r#"package main
+
+import (
+	"bufio"
+	"fmt"
+	"os"
+)
+
+func main() {
+	in := bufio.NewReader(os.Stdin)
+	out := bufio.NewWriter(os.Stdout)
+	defer out.Flush()
+
+	var n int
+	fmt.Fscan(in, &n)
+}
+"#,
+    },
+];
+
+// Find the preset by the name
+pub fn preset(name: &str) -> Option<&'static Preset> {
+    PRESETS
+        .iter()
+        .find(|p: &&Preset| p.name.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+
+use regex::{Captures, Regex};
+
+use reqwest::Client;
+
+use scraper::{ElementRef, Html, Selector};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{save_task_cache, task_cache, TaskCache};
+use crate::error::AttestError;
+use crate::show::render_text;
+use crate::task::Task;
+use crate::test::IO;
+use crate::utils::{make_client, now_secs, request, to_html};
+
+// Metadata of the task
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProblemMeta {
+    pub contest: String,
+    pub task: String,
+    pub url: String,
+    // Seconds since UNIX epoch
+    pub fetched_at: u64,
+    pub title: String,
+    pub score: Option<u64>,
+    // Megabytes
+    pub memory_limit: Option<u64>,
+    pub constraints: Vec<String>,
+    pub input_format: String,
+    // Whether the statement says any of multiple answers is accepted
+    pub multiple_answers: bool,
+    // Absolute or relative error accepted by the statement
+    pub tolerance: Option<f64>,
+}
+
+impl ProblemMeta {
+    // Constructor for the task fetched now
+    pub(crate) fn new(task: &Task) -> Self {
+        ProblemMeta {
+            contest: task.contest.clone(),
+            task: task.id.clone(),
+            url: task.url(),
+            fetched_at: now_secs(),
+            ..Default::default()
+        }
+    }
+}
+
+// Error of extracting examples from the task page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleError {
+    // No headings of examples were found
+    NotFound,
+    // The example has only input or only output
+    Unpaired(usize),
+}
+
+impl Display for SampleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SampleError::NotFound => {
+                write!(f, "No examples were found. Please input the question page")
+            }
+            SampleError::Unpaired(n) => {
+                write!(f, "The example {} does not have both input and output", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SampleError {}
+
+// Download examples, time limit and metadata of the task
+pub async fn download_task(client: &Client, task: &Task) -> Result<TaskCache> {
+    let text: String = request(client, &task.url()).await?;
+
+    let html: Html = to_html(text);
+
+    let mut meta: ProblemMeta = ProblemMeta::new(task);
+
+    meta.title = get_title(&html);
+
+    meta.score = get_score(&html);
+
+    meta.memory_limit = get_memory_limit(&html);
+
+    meta.constraints = get_constraints(&html);
+
+    meta.input_format = get_input_format(&html);
+
+    meta.multiple_answers = is_multiple_answers(&html);
+
+    meta.tolerance = get_tolerance(&html);
+
+    Ok(TaskCache {
+        examples: select_samples(&html).with_context(|| {
+            AttestError::Scrape(format!("failed to find examples in {}", task.url()))
+        })?,
+        time_limit: get_time_limit(&html).unwrap_or(u128::MAX),
+        meta,
+        statement: get_statement(&html),
+    })
+}
+
+// Get the cache of the task, or download it if it is not cached or cached by older versions
+pub async fn cached_or_download(task: &Task) -> Result<TaskCache> {
+    if let Some(cache) =
+        task_cache(task).filter(|c: &TaskCache| !c.statement.is_empty() && !c.meta.title.is_empty())
+    {
+        return Ok(cache);
+    }
+
+    let cache: TaskCache = download_task(&make_client(), task).await?;
+
+    save_task_cache(task, &cache)?;
+
+    Ok(cache)
+}
+
+// Get the title like "A - Generalized ABC"
+pub fn get_title(html: &Html) -> String {
+    let selector: Selector = Selector::parse("title").unwrap();
+
+    html.select(&selector)
+        .next()
+        .map(|e: ElementRef| e.text().collect::<String>().trim().to_string())
+        .unwrap_or_default()
+}
+
+// Score of the task like `配点 : 100 点`
+static SCORE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:配点|Score)\s*:\s*(\d+)").unwrap());
+
+// Get the score
+pub fn get_score(html: &Html) -> Option<u64> {
+    let selector: Selector = Selector::parse("#task-statement").unwrap();
+
+    let text: String = html.select(&selector).next()?.text().collect();
+
+    SCORE.captures(&text)?[1].parse().ok()
+}
+
+// Memory limit like `Memory Limit: 1024 MiB`, where older tasks show it in MB
+static MEMORY_LIMIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Memory Limit: (\d+) ?([KMG])i?B").unwrap());
+
+// Get memory limit in megabytes
+pub fn get_memory_limit(html: &Html) -> Option<u64> {
+    let selector: Selector = Selector::parse(r#"div[class="col-sm-12"] > p"#).unwrap();
+
+    let text: String = html.select(&selector).next()?.text().collect();
+
+    let captures: Captures = MEMORY_LIMIT.captures(&text)?;
+
+    let value: u64 = captures[1].parse().ok()?;
+
+    Some(match &captures[2] {
+        "K" => value / 1024,
+        "G" => value * 1024,
+        _ => value,
+    })
+}
+
+// Find the section of the statement by its heading like "制約" or "Constraints"
+fn find_section<'a>(html: &'a Html, headings: &[&str]) -> Option<ElementRef<'a>> {
+    let roots: [&str; 3] = [
+        r#"span[class="lang-ja"]"#,
+        r#"span[class="lang-en"]"#,
+        "#task-statement",
+    ];
+
+    let section: Selector = Selector::parse("section").unwrap();
+
+    let heading: Selector = Selector::parse("h3").unwrap();
+
+    roots.iter().find_map(|root: &&str| {
+        let root: ElementRef = html.select(&Selector::parse(root).unwrap()).next()?;
+
+        root.select(&section).find(|s: &ElementRef| {
+            s.select(&heading).next().is_some_and(|h: ElementRef| {
+                headings.contains(&h.text().collect::<String>().trim())
+            })
+        })
+    })
+}
+
+// Get the constraints with LaTeX converted to Unicode
+pub fn get_constraints(html: &Html) -> Vec<String> {
+    let Some(section) = find_section(html, &["制約", "Constraints"]) else {
+        return Vec::new();
+    };
+
+    let item: Selector = Selector::parse("li").unwrap();
+
+    section.select(&item).map(render_text).collect()
+}
+
+// Get the input format with LaTeX converted to Unicode
+pub fn get_input_format(html: &Html) -> String {
+    let pre: Selector = Selector::parse("pre").unwrap();
+
+    find_section(html, &["入力", "Input"])
+        .and_then(|section: ElementRef| section.select(&pre).next())
+        .map(render_text)
+        .unwrap_or_default()
+}
+
+// Get HTML of the problem statement
+pub fn get_statement(html: &Html) -> String {
+    let selector: Selector = Selector::parse("#task-statement").unwrap();
+
+    html.select(&selector)
+        .next()
+        .map(|e: ElementRef| e.html())
+        .unwrap_or_default()
+}
+
+// Select examples from Html
+//
+// Examples are paired by the number in their headings like "Sample Input 1" and "出力例 1",
+// looking at the Japanese statement, the English statement and the whole page in order
+pub fn select_samples(html: &Html) -> Result<Vec<IO>, SampleError> {
+    let roots: [&str; 4] = [
+        r#"span[class="lang-ja"]"#,
+        r#"span[class="lang-en"]"#,
+        "#task-statement",
+        "body",
+    ];
+
+    for root in roots {
+        let selector: Selector = Selector::parse(root).unwrap();
+
+        let Some(root) = html.select(&selector).next() else {
+            continue;
+        };
+
+        if let Some(examples) = samples_in(root)? {
+            return Ok(examples);
+        }
+    }
+
+    Err(SampleError::NotFound)
+}
+
+// Headings of examples like `Sample Input 1` or `入力例 1`
+static SAMPLE_INPUT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:Sample Input|入力例)\s*(\d+)").unwrap());
+static SAMPLE_OUTPUT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:Sample Output|出力例)\s*(\d+)").unwrap());
+
+// Select examples under the element, or `None` if there are no headings of examples
+fn samples_in(root: ElementRef) -> Result<Option<Vec<IO>>, SampleError> {
+    let heading: Selector = Selector::parse("h3").unwrap();
+
+    let mut samples: BTreeMap<usize, (Option<String>, Option<String>)> = BTreeMap::new();
+
+    for h in root.select(&heading) {
+        let title: String = h.text().collect();
+
+        let number = |re: &Regex| -> Option<usize> {
+            re.captures(&title)
+                .and_then(|c: Captures| c[1].parse().ok())
+        };
+
+        let (n, is_input): (usize, bool) = if let Some(n) = number(&SAMPLE_INPUT) {
+            (n, true)
+        } else if let Some(n) = number(&SAMPLE_OUTPUT) {
+            (n, false)
+        } else {
+            continue;
+        };
+
+        let Some(pre) = h
+            .next_siblings()
+            .filter_map(ElementRef::wrap)
+            .next()
+            .filter(|e: &ElementRef| e.value().name() == "pre")
+        else {
+            continue;
+        };
+
+        let text: String = pre.text().collect();
+
+        let entry: &mut (Option<String>, Option<String>) = samples.entry(n).or_default();
+
+        let slot: &mut Option<String> = if is_input { &mut entry.0 } else { &mut entry.1 };
+
+        slot.get_or_insert(text);
+    }
+
+    if samples.is_empty() {
+        return Ok(None);
+    }
+
+    samples
+        .into_iter()
+        .map(|(n, sample)| match sample {
+            (Some(input), Some(output)) => Ok(IO::new(input, output)),
+            _ => Err(SampleError::Unpaired(n)),
+        })
+        .collect::<Result<Vec<IO>, SampleError>>()
+        .map(Some)
+}
+
+// Time limit like `Time Limit: 2 sec` or `Time Limit: 500 msec`
+static TIME_LIMIT_SEC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("Time Limit: (.+) sec").unwrap());
+static TIME_LIMIT_MSEC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new("Time Limit: (.+) msec").unwrap());
+
+// Get time limit in milliseconds
+pub fn get_time_limit(html: &Html) -> Option<u128> {
+    let selector: Selector = Selector::parse(r#"div[class="col-sm-12"] > p"#).unwrap();
+
+    let t: String = html
+        .select(&selector)
+        .next()
+        .map(|p: ElementRef| p.text().collect())
+        .unwrap_or_default();
+
+    if let Some(sec) = TIME_LIMIT_SEC
+        .captures(&t)
+        .and_then(|s: Captures| s[1].parse::<f64>().ok())
+    {
+        Some((sec * 1000.) as u128)
+    } else {
+        TIME_LIMIT_MSEC
+            .captures(&t)
+            .and_then(|s: Captures| s[1].parse::<u128>().ok())
+    }
+}
+
+// Sentence saying any of multiple answers is accepted
+static MULTIPLE_ANSWERS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(print any of them|may print any|any of them will be accepted|(どれ|いずれ)を出力しても)",
+    )
+    .unwrap()
+});
+
+// Check if the statement says any of multiple answers is accepted
+pub fn is_multiple_answers(html: &Html) -> bool {
+    let selector: Selector = Selector::parse("#task-statement").unwrap();
+
+    let Some(statement) = html.select(&selector).next() else {
+        return false;
+    };
+
+    let text: String = statement.text().collect();
+
+    MULTIPLE_ANSWERS.is_match(&text)
+}
+
+// Sentence about the absolute or relative error with its exponent like `10^{-6}`
+static TOLERANCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:absolute or relative|relative or absolute|絶対誤差(?:または|か)相対誤差|相対誤差(?:または|か)絶対誤差)[^。]*?10\s*\^\s*\{?\s*[-−]\s*(\d+)",
+    )
+    .unwrap()
+});
+
+// Get the absolute or relative error like 10^{-6} accepted by the statement
+pub fn get_tolerance(html: &Html) -> Option<f64> {
+    let selector: Selector = Selector::parse("#task-statement").unwrap();
+
+    let text: String = html.select(&selector).next()?.text().collect();
+
+    let exponent: i32 = TOLERANCE.captures(&text)?[1].parse().ok()?;
+
+    Some(10f64.powi(-exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(body: &str) -> Result<Vec<IO>, SampleError> {
+        select_samples(&Html::parse_document(body))
+    }
+
+    #[test]
+    fn samples_are_paired_by_number() {
+        let examples: Vec<IO> = samples(
+            r#"<span class="lang-ja">
+<h3>入力例 2</h3><pre>3
+</pre>
+<h3>出力例 1</h3><pre>2
+</pre>
+<h3>入力例 1</h3><pre>1
+</pre>
+<h3>出力例 2</h3><pre>4
+</pre>
+</span>"#,
+        )
+        .unwrap();
+
+        let pairs: Vec<(&str, &str)> = examples
+            .iter()
+            .map(|io: &IO| (io.input.as_str(), io.output.as_str()))
+            .collect();
+
+        assert_eq!(pairs, [("1\n", "2\n"), ("3\n", "4\n")]);
+    }
+
+    #[test]
+    fn japanese_samples_are_preferred() {
+        let examples: Vec<IO> = samples(
+            r#"<span class="lang-ja"><h3>入力例 1</h3><pre>ja</pre><h3>出力例 1</h3><pre>ja</pre></span>
+<span class="lang-en"><h3>Sample Input 1</h3><pre>en</pre><h3>Sample Output 1</h3><pre>en</pre></span>"#,
+        )
+        .unwrap();
+
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].input, "ja");
+    }
+
+    #[test]
+    fn headings_without_pre_are_skipped() {
+        let examples: Vec<IO> = samples(
+            r#"<div id="task-statement">
+<h3>Sample Input 1</h3><p>note</p><pre>x</pre>
+<h3>Sample Input 1</h3><pre>5</pre>
+<h3>Sample Output 1</h3><pre>25</pre>
+</div>"#,
+        )
+        .unwrap();
+
+        assert_eq!(examples.len(), 1);
+        assert_eq!(
+            (examples[0].input.as_str(), examples[0].output.as_str()),
+            ("5", "25")
+        );
+    }
+
+    #[test]
+    fn unpaired_samples_are_errors() {
+        let result: Result<Vec<IO>, SampleError> = samples(
+            r#"<h3>Sample Input 1</h3><pre>1</pre><h3>Sample Output 1</h3><pre>1</pre>
+<h3>Sample Input 2</h3><pre>2</pre>"#,
+        );
+
+        assert_eq!(result.err(), Some(SampleError::Unpaired(2)));
+    }
+
+    #[test]
+    fn memory_limit_in_mib_and_mb() {
+        let page = |limit: &str| -> Html {
+            Html::parse_document(&format!(
+                r#"<div class="col-sm-12"><span class="h2">A - Test</span>
+<p>Time Limit: 2 sec / Memory Limit: {}</p></div>"#,
+                limit
+            ))
+        };
+
+        assert_eq!(get_memory_limit(&page("1024 MiB")), Some(1024));
+        assert_eq!(get_memory_limit(&page("1024 MB")), Some(1024));
+        assert_eq!(get_memory_limit(&page("2 GiB")), Some(2048));
+        assert_eq!(get_memory_limit(&page("262144 KiB")), Some(256));
+        assert_eq!(get_time_limit(&page("1024 MiB")), Some(2000));
+    }
+
+    #[test]
+    fn no_samples_are_not_found() {
+        assert_eq!(
+            samples("<h3>Problem Statement</h3><pre>x</pre>").err(),
+            Some(SampleError::NotFound)
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use anyhow::Result;
+
+use scraper::{ElementRef, Html, Selector};
+
+use crate::cache::{current_task, TaskCache};
+use crate::error::AttestError;
+use crate::problem::cached_or_download;
+use crate::task::Task;
+use crate::utils::Marker;
+
+// Show the problem statement of the task
+pub async fn show(url: Option<String>, en: bool) -> Result<()> {
+    let task: Task = match url {
+        Some(u) => Task::from_url(&u),
+        None => current_task(),
+    }
+    .ok_or_else(|| AttestError::Usage(String::from("Please input the URL of the task")))?;
+
+    let cache: TaskCache = cached_or_download(&task).await?;
+
+    let html: Html = Html::parse_fragment(&cache.statement);
+
+    let lang: Selector = Selector::parse(if en {
+        r#"span[class="lang-en"]"#
+    } else {
+        r#"span[class="lang-ja"]"#
+    })
+    .unwrap();
+
+    let root: ElementRef = html
+        .select(&lang)
+        .next()
+        .unwrap_or_else(|| html.root_element());
+
+    let mut text: String = String::new();
+
+    render(root, &mut text, false);
+
+    println!("{} \x1b[35m{}\x1b[m", Marker::X, cache.meta.title);
+
+    println!("{} {}", Marker::X, task.url());
+
+    if cache.time_limit != u128::MAX {
+        println!("{} Time Limit: {} ms", Marker::X, cache.time_limit);
+    }
+
+    println!("{}", squeeze_blank_lines(&text));
+
+    Ok(())
+}
+
+// Render the content of the element as terminal text
+pub fn render_text(elem: ElementRef) -> String {
+    let mut text: String = String::new();
+
+    render_children(elem, &mut text, elem.value().name() == "pre");
+
+    squeeze_blank_lines(&text)
+}
+
+// Render the children of the element as terminal text
+fn render_children(elem: ElementRef, out: &mut String, in_pre: bool) {
+    for child in elem.children() {
+        if let Some(e) = ElementRef::wrap(child) {
+            render(e, out, in_pre);
+        } else if let Some(t) = child.value().as_text() {
+            if in_pre {
+                out.push_str(t);
+            } else {
+                push_collapsed(out, t);
+            }
+        }
+    }
+}
+
+// Render the element as terminal text
+fn render(elem: ElementRef, out: &mut String, in_pre: bool) {
+    let class: &str = elem.value().attr("class").unwrap_or("");
+
+    // Copy buttons of examples
+    if class.contains("btn") {
+        return;
+    }
+
+    match elem.value().name() {
+        "h3" => {
+            let mut title: String = String::new();
+            render_children(elem, &mut title, false);
+            out.push_str(&format!("\n\n\x1b[1;4m{}\x1b[m\n", title.trim()));
+        }
+        "p" | "div" | "section" => {
+            out.push('\n');
+            render_children(elem, out, in_pre);
+            out.push('\n');
+        }
+        "li" => {
+            let mut item: String = String::new();
+            render_children(elem, &mut item, in_pre);
+            out.push_str(&format!("\n  • {}", item.trim()));
+        }
+        "ul" | "ol" => {
+            render_children(elem, out, in_pre);
+            out.push('\n');
+        }
+        "pre" => {
+            let mut block: String = String::new();
+            render_children(elem, &mut block, true);
+            out.push('\n');
+            for line in block.trim_end().lines() {
+                out.push_str(&format!("    {}\n", line));
+            }
+        }
+        "var" => {
+            let tex: String = elem.text().collect();
+            out.push_str(&latex_to_unicode(&tex));
+        }
+        "br" => out.push('\n'),
+        "hr" => out.push_str("\n────────\n"),
+        "img" => out.push_str("[image]"),
+        "strong" | "b" => {
+            out.push_str("\x1b[1m");
+            render_children(elem, out, in_pre);
+            out.push_str("\x1b[m");
+        }
+        _ => render_children(elem, out, in_pre),
+    }
+}
+
+// Push the text collapsing whitespaces like HTML does
+fn push_collapsed(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.ends_with([' ', '\n']) && !out.is_empty() {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+// Remove spaces at the end of lines and blank lines more than one
+fn squeeze_blank_lines(text: &str) -> String {
+    let mut out: String = String::new();
+
+    let mut blank: bool = true;
+
+    for line in text.lines().map(|l: &str| l.trim_end()) {
+        if line.is_empty() {
+            if !blank {
+                out.push('\n');
+            }
+            blank = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+// Convert LaTeX in the statement like `1 \leq N \leq 10^5` to Unicode like `1 ≤ N ≤ 10⁵`
+fn latex_to_unicode(tex: &str) -> String {
+    let chars: Vec<char> = tex.chars().collect();
+
+    let mut i: usize = 0;
+
+    let converted: String = convert(&chars, &mut i, false);
+
+    converted
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// Convert from `chars[*i]` until the end or the closing brace if `in_group`
+fn convert(chars: &[char], i: &mut usize, in_group: bool) -> String {
+    let mut out: String = String::new();
+
+    while *i < chars.len() {
+        let c: char = chars[*i];
+        *i += 1;
+
+        match c {
+            '}' if in_group => break,
+            '{' => out.push_str(&convert(chars, i, true)),
+            '}' => {}
+            '^' => {
+                let arg: String = argument(chars, i);
+                out.push_str(&script(&arg, '^'));
+            }
+            '_' => {
+                let arg: String = argument(chars, i);
+                out.push_str(&script(&arg, '_'));
+            }
+            '\\' => out.push_str(&command(chars, i)),
+            '~' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+// Read the argument of the command or script, which is a group or a character
+fn argument(chars: &[char], i: &mut usize) -> String {
+    while *i < chars.len() && chars[*i] == ' ' {
+        *i += 1;
+    }
+
+    match chars.get(*i) {
+        Some('{') => {
+            *i += 1;
+            convert(chars, i, true)
+        }
+        Some('\\') => {
+            *i += 1;
+            command(chars, i)
+        }
+        Some(&c) => {
+            *i += 1;
+            c.to_string()
+        }
+        None => String::new(),
+    }
+}
+
+// Convert the command after the backslash
+fn command(chars: &[char], i: &mut usize) -> String {
+    let start: usize = *i;
+
+    while *i < chars.len() && chars[*i].is_ascii_alphabetic() {
+        *i += 1;
+    }
+
+    if start == *i {
+        // Escaped characters like `\{` and spaces like `\,`
+        let Some(&c) = chars.get(*i) else {
+            return String::new();
+        };
+        *i += 1;
+        return match c {
+            ',' | ';' | ':' | ' ' => " ".to_string(),
+            '!' => String::new(),
+            '\\' => "\n".to_string(),
+            c => c.to_string(),
+        };
+    }
+
+    let name: String = chars[start..*i].iter().collect();
+
+    // Spaces after commands are ignored in LaTeX
+    while *i < chars.len() && chars[*i] == ' ' {
+        *i += 1;
+    }
+
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator: String = argument(chars, i);
+            let denominator: String = argument(chars, i);
+            format!("{}/{}", wrap(&numerator), wrap(&denominator))
+        }
+        "binom" => {
+            let n: String = argument(chars, i);
+            let k: String = argument(chars, i);
+            format!("C({}, {})", n, k)
+        }
+        "sqrt" => format!("√{}", wrap(&argument(chars, i))),
+        "mathrm" | "text" | "textrm" | "mathbf" | "textbf" | "mathit" | "operatorname"
+        | "mathtt" | "texttt" => argument(chars, i),
+        "left" | "right" | "displaystyle" | "limits" | "big" | "Big" | "bigl" | "bigr" => {
+            String::new()
+        }
+        name => match symbol(name) {
+            // Relations and operators are padded since spaces around them are ignored
+            Some(s) if "≤≥≠<>×∈∉⊂⊆→←⇒⇔≡≈±".contains(s) => {
+                format!(" {} ", s)
+            }
+            Some(s) => s.to_string(),
+            None => name.to_string(),
+        },
+    }
+}
+
+// Unicode of the LaTeX symbol
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "leq" | "le" | "leqq" | "leqslant" => "≤",
+        "geq" | "ge" | "geqq" | "geqslant" => "≥",
+        "neq" | "ne" => "≠",
+        "lt" => "<",
+        "gt" => ">",
+        "times" => "×",
+        "cdot" => "·",
+        "cdots" | "ldots" | "dots" => "…",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "pm" => "±",
+        "infty" => "∞",
+        "sum" => "Σ",
+        "prod" => "Π",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" => "∅",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "Rightarrow" => "⇒",
+        "Leftrightarrow" | "iff" => "⇔",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "mid" | "vert" | "lvert" | "rvert" => "|",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "equiv" => "≡",
+        "approx" => "≈",
+        "oplus" => "⊕",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "neg" | "lnot" => "¬",
+        "forall" => "∀",
+        "exists" => "∃",
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "theta" => "θ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "pi" => "π",
+        "sigma" => "σ",
+        "omega" => "ω",
+        "quad" => "  ",
+        "qquad" => "    ",
+        "bmod" => "mod",
+        _ => return None,
+    })
+}
+
+// Wrap the text with parentheses if it is longer than a character
+fn wrap(text: &str) -> String {
+    if text.chars().count() > 1 {
+        format!("({})", text)
+    } else {
+        text.to_string()
+    }
+}
+
+// Convert the superscript or subscript to Unicode, or `^(...)` if it cannot be converted
+fn script(text: &str, kind: char) -> String {
+    let converted: Option<String> = text
+        .chars()
+        .map(|c: char| {
+            if kind == '^' {
+                superscript(c)
+            } else {
+                subscript(c)
+            }
+        })
+        .collect();
+
+    converted.unwrap_or_else(|| format!("{}{}", kind, wrap(text)))
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'i' => 'ⁱ',
+        'k' => 'ᵏ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relations_and_scripts() {
+        assert_eq!(latex_to_unicode(r"1 \leq N \leq 10^5"), "1 ≤ N ≤ 10⁵");
+        assert_eq!(latex_to_unicode(r"A_i \neq A_j"), "Aᵢ ≠ Aⱼ");
+    }
+
+    #[test]
+    fn groups_and_commands() {
+        assert_eq!(latex_to_unicode(r"\frac{N}{2}"), "N/2");
+        assert_eq!(latex_to_unicode(r"\sqrt{N}"), "√N");
+        assert_eq!(latex_to_unicode(r"\mathrm{mod}"), "mod");
+        assert_eq!(latex_to_unicode(r"\{1, 2\}"), "{1, 2}");
+    }
+
+    #[test]
+    fn unknown_commands_are_kept() {
+        assert_eq!(latex_to_unicode(r"\foo"), "foo");
+    }
+}
@@ -1,102 +1,239 @@
 use std::collections::HashMap;
-use std::fs::{create_dir_all, File};
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::fs::{self, create_dir_all, remove_dir_all, File};
+use std::io::{prelude::*, stdin, stdout, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::Result;
-use dirs::home_dir;
+use anyhow::{bail, Context, Result};
 use reqwest::cookie::{Cookie, Jar};
 use reqwest::{Client, Response};
 use scraper::{ElementRef, Html, Selector};
-use toml::Value;
+use toml::{map::Map, Value};
 
+use crate::cache::{
+    current_contest, current_task, lang_cache, save_current_contest, save_lang_cache, TaskCache,
+};
+use crate::config::{global_config_path, layered_items, report_warnings, LayeredItems, Problem};
+use crate::error::AttestError;
+use crate::preset::{preset, Preset, PRESETS};
+use crate::problem::{cached_or_download, ProblemMeta};
+use crate::task::{contest_from_url, task_list, Task};
+use crate::test::BUILD_STEPS_CACHE;
 use crate::utils::{
-    create_err, file_read_to_string, get_item_toml, lang_select, make_client, request,
+    create_err, get_item_toml, global_dir, items_toml, lang_select, make_client, request,
     set_item_toml, to_html, write_err, Marker,
 };
 
-// Initialize
-pub fn init() {
-    File::create("./attest.toml").unwrap_or_else(|_| panic!("{}", create_err("./attest.toml")));
+// Initialize the project with the preset of the language
+//
+// Without the preset, ask it if the input is a terminal.
+// The existing `attest.toml` and program file are kept unless they are allowed to be overwritten
+pub fn init(preset_name: Option<String>, force: bool) -> Result<()> {
+    let interactive: bool = stdin().is_terminal();
+
+    let preset: Option<&Preset> = match preset_name {
+        Some(name) => Some(preset(&name).ok_or_else(|| {
+            AttestError::Usage(format!(
+                r#"the preset "{}" does not exist. Choose from {}"#,
+                name,
+                PRESETS.map(|p: Preset| p.name).join(", ")
+            ))
+        })?),
+        None if interactive => ask_preset()?,
+        None => None,
+    };
+
+    let has_config: bool =
+        fs::read_to_string("./attest.toml").is_ok_and(|s: String| !s.trim().is_empty());
 
-    create_dir_all("./.attest").unwrap_or_else(|_| panic!("{}", create_err("./.attest")));
+    if has_config && !force {
+        let overwrite: bool = interactive
+            && ask(r#""attest.toml" already exists. Overwrite it? [y/N]"#)?
+                .eq_ignore_ascii_case("y");
 
-    File::create("./.attest/before.txt")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/before.txt")));
+        if !overwrite {
+            bail!(AttestError::Usage(String::from(
+                r#""attest.toml" already exists. Use --force to overwrite it"#
+            )));
+        }
+    }
 
-    File::create("./.attest/before_setting.toml")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/before_setting.toml")));
+    let mut items: Map<String, Value> = Map::new();
 
-    File::create("./.attest/url.txt")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/url.txt")));
+    if let Some(preset) = preset {
+        items.insert(String::from("lang"), Value::String(preset.lang.to_string()));
 
-    File::create("./.attest/examples.json")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/examples.json")));
+        // Cargo packages derive the commands by themselves
+        if !(preset.name == "rust" && Path::new("./Cargo.toml").is_file()) {
+            let to_array = |v: &[&str]| -> Value {
+                Value::Array(
+                    v.iter()
+                        .map(|s: &&str| Value::String(s.to_string()))
+                        .collect(),
+                )
+            };
 
-    File::create("./.attest/deps_caches.json")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/deps_caches.json")));
+            if !preset.build.is_empty() {
+                items.insert(String::from("build"), to_array(preset.build));
+            }
 
-    File::create("./.attest/time_limit.txt")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/time_limit.txt")));
+            items.insert(String::from("run"), to_array(preset.run));
+
+            items.insert(
+                String::from("file_path"),
+                Value::String(preset.file_path.to_string()),
+            );
+
+            if !Path::new(preset.file_path).exists() {
+                let mut f: File =
+                    File::create(preset.file_path).with_context(|| create_err(preset.file_path))?;
+                write!(&mut f, "{}", preset.template)
+                    .with_context(|| write_err(preset.file_path))?;
+            }
+        }
+    }
+
+    let mut f: File = File::create("./attest.toml").with_context(|| create_err("./attest.toml"))?;
+    write!(&mut f, "{}", items).with_context(|| write_err("./attest.toml"))?;
+
+    create_dir_all("./.attest/tasks").with_context(|| create_err("./.attest/tasks"))?;
+
+    if !Path::new(BUILD_STEPS_CACHE).exists() {
+        File::create(BUILD_STEPS_CACHE).with_context(|| create_err(BUILD_STEPS_CACHE))?;
+    }
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
+}
+
+// Ask the preset to use, where the empty answer means no preset
+fn ask_preset() -> Result<Option<&'static Preset>> {
+    println!("{} Select the language", Marker::X);
+
+    for (i, p) in PRESETS.iter().enumerate() {
+        println!("    {}: {} ({})", i + 1, p.name, p.lang_name);
+    }
+
+    loop {
+        let answer: String = ask("Number or name (empty for none)")?;
+
+        if answer.is_empty() {
+            return Ok(None);
+        }
+
+        let found: Option<&Preset> = match answer.parse::<usize>() {
+            Ok(n) => PRESETS.get(n.wrapping_sub(1)),
+            Err(_) => preset(&answer),
+        };
+
+        match found {
+            Some(p) => return Ok(Some(p)),
+            None => eprintln!("{} {} is not in the list", Marker::Minus, answer),
+        }
+    }
+}
+
+// Show the question and read the answer from stdin
+fn ask(question: &str) -> Result<String> {
+    print!("{} {} ", Marker::X, question);
+
+    stdout().flush()?;
+
+    let mut answer: String = String::new();
+
+    stdin().read_line(&mut answer)?;
+
+    Ok(answer.trim().to_string())
+}
+
+// Show the settings in effect with where each value comes from
+pub fn show_set(profile: Option<&str>) -> Result<()> {
+    let (items, warnings): (LayeredItems, Vec<Problem>) = layered_items(profile)?;
+
+    report_warnings(&warnings);
+
+    for (key, (value, source)) in &items {
+        println!("{} = {} \x1b[90m# {}\x1b[m", key, value, source);
+    }
+
+    Ok(())
 }
 
-// Show settings
-pub fn show_set() {
-    let r = file_read_to_string("./attest.toml");
+// Path of the settings file to write
+//
+// The directory of the global settings is created if it doesn't exist
+fn settings_path(global: bool) -> Result<PathBuf> {
+    if !global {
+        return Ok(PathBuf::from("./attest.toml"));
+    }
+
+    let path: PathBuf = global_config_path()?;
+
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir).with_context(|| create_err(dir))?;
+    }
 
-    println!("{}", &r.trim_end());
+    Ok(path)
 }
 
 // Set the build command
-pub fn set_build(commands: Vec<String>) {
+pub fn set_build(commands: Vec<String>, global: bool) -> Result<()> {
     let values: Vec<Value> = commands
         .into_iter()
         .map(|v: String| Value::String(v))
         .collect();
 
-    set_item_toml("./attest.toml", "build", Value::Array(values));
+    set_item_toml(settings_path(global)?, "build", Value::Array(values))?;
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
 }
 
 // Set the run command
-pub fn set_run(commands: Vec<String>) {
+pub fn set_run(commands: Vec<String>, global: bool) -> Result<()> {
     let values: Vec<Value> = commands
         .into_iter()
         .map(|v: String| Value::String(v))
         .collect();
 
-    set_item_toml("./attest.toml", "run", Value::Array(values));
+    set_item_toml(settings_path(global)?, "run", Value::Array(values))?;
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
 }
 
 // Set the test command
-pub fn set_test(commands: Vec<String>) {
+pub fn set_test(commands: Vec<String>, global: bool) -> Result<()> {
     let values: Vec<Value> = commands
         .into_iter()
         .map(|v: String| Value::String(v))
         .collect();
 
-    set_item_toml("./attest.toml", "test", Value::Array(values));
+    set_item_toml(settings_path(global)?, "test", Value::Array(values))?;
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
 }
 
 // Set the program file
-pub fn set_file(name: String) {
-    set_item_toml("./attest.toml", "file_path", Value::String(name));
+pub fn set_file(name: String, global: bool) -> Result<()> {
+    set_item_toml(settings_path(global)?, "file_path", Value::String(name))?;
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
 }
 
 // Set the other depends file
-pub fn set_deps_file(paths: Vec<String>, add: bool) {
+pub fn set_deps_file(paths: Vec<String>, add: bool, global: bool) -> Result<()> {
+    let path: PathBuf = settings_path(global)?;
+
     let mut now = if add {
-        get_item_toml("./attest.toml", "deps_files")
+        get_item_toml(&path, "deps_files")?
             .unwrap_or(Value::Array(Vec::new()))
             .as_array()
             .unwrap_or(&Vec::new())
@@ -109,12 +246,201 @@ pub fn set_deps_file(paths: Vec<String>, add: bool) {
 
     now.append(&mut list);
 
-    set_item_toml("./attest.toml", "deps_files", Value::Array(now));
+    set_item_toml(&path, "deps_files", Value::Array(now))?;
 
     println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
 }
 
-pub async fn login(user_name: String, password: String) {
+// Show or Set the contest to use task aliases
+pub async fn contest(contest: Option<String>) -> Result<()> {
+    let contest: String = match contest {
+        Some(c) => {
+            let c: String = contest_from_url(&c).unwrap_or(c);
+            save_current_contest(&c)?;
+            c
+        }
+        None => current_contest().ok_or_else(|| {
+            AttestError::Usage(String::from(
+                "The contest is not set. Please input the contest",
+            ))
+        })?,
+    };
+
+    println!("{} {}", Marker::Plus, contest);
+
+    let list: Vec<(String, Task)> = task_list(&contest).await?;
+
+    if list.is_empty() {
+        println!(
+            "{}",
+            Marker::minus("No tasks were found. You may not login or the contest may not start")
+        );
+    }
+
+    for (letter, task) in &list {
+        println!("{} {}", letter, task.id);
+    }
+
+    Ok(())
+}
+
+// Get langs from the submit page of the latest contest
+async fn latest_langs() -> Result<Vec<(String, String)>> {
+    let client: Client = make_client();
+
+    let past_contests: String = request(
+        &client,
+        "https://atcoder.jp/contests/archive?ratedType=1&category=0&keyword=",
+    )
+    .await?;
+
+    let html: Html = to_html(past_contests);
+
+    let selector: Selector =
+        Selector::parse(r#"div[class="table-responsive"] tbody td span + a"#).unwrap();
+
+    let href: &str = html
+        .select(&selector)
+        .next()
+        .and_then(|e: ElementRef| e.attr("href"))
+        .ok_or_else(|| AttestError::Scrape(String::from("the latest contest was not found")))?;
+
+    let url: String = String::from("https://atcoder.jp") + href + "/submit";
+
+    let text: String = request(&client, &url).await?;
+
+    let html: Html = to_html(text);
+
+    let selector: Selector = Selector::parse(r#"label[for="select-lang"] + div select"#).unwrap();
+
+    let selected: Html = to_html(
+        html.select(&selector)
+            .next()
+            .ok_or_else(|| AttestError::Scrape(String::from("the langs were not found")))?
+            .html(),
+    );
+
+    lang_select(&selected)
+}
+
+// Clear the cache of tasks
+pub fn clear_cache(global: bool) -> Result<()> {
+    let dir: PathBuf = if global {
+        global_dir()?.join("cache")
+    } else {
+        PathBuf::from("./.attest/tasks")
+    };
+
+    if dir.is_dir() {
+        remove_dir_all(&dir).with_context(|| {
+            AttestError::Io(format!("something went wrong removing {}", dir.display()))
+        })?;
+    }
+
+    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
+}
+
+// Show the metadata of the task
+pub async fn info(url: Option<String>) -> Result<()> {
+    let task: Task = match url {
+        Some(u) => Task::from_url(&u),
+        None => current_task(),
+    }
+    .ok_or_else(|| AttestError::Usage(String::from("Please input the URL of the task")))?;
+
+    let cache: TaskCache = cached_or_download(&task).await?;
+
+    let meta: &ProblemMeta = &cache.meta;
+
+    println!("{} \x1b[35m{}\x1b[m", Marker::X, meta.title);
+    println!("{} contest: {}", Marker::X, meta.contest);
+    println!("{} task: {}", Marker::X, meta.task);
+    println!("{} url: {}", Marker::X, meta.url);
+
+    if let Some(score) = meta.score {
+        println!("{} score: {}", Marker::X, score);
+    }
+
+    if cache.time_limit != u128::MAX {
+        println!("{} time limit: {} ms", Marker::X, cache.time_limit);
+    }
+
+    if let Some(memory_limit) = meta.memory_limit {
+        println!("{} memory limit: {} MB", Marker::X, memory_limit);
+    }
+
+    println!("{} examples: {}", Marker::X, cache.examples.len());
+
+    if meta.multiple_answers {
+        println!("{} multiple answers: accepted", Marker::X);
+    }
+
+    if let Some(tolerance) = meta.tolerance {
+        println!("{} tolerance: {:e}", Marker::X, tolerance);
+    }
+
+    if !meta.constraints.is_empty() {
+        println!("{} constraints:", Marker::X);
+        for c in &meta.constraints {
+            println!("  • {}", c);
+        }
+    }
+
+    if !meta.input_format.is_empty() {
+        println!("{} input format:", Marker::X);
+        for line in meta.input_format.lines() {
+            println!("    {}", line);
+        }
+    }
+
+    Ok(())
+}
+
+// Use the profile
+pub fn use_profile(name: String) -> Result<()> {
+    let exists: bool = get_item_toml("./attest.toml", "profile")?
+        .is_some_and(|v: Value| v.get(&name).is_some_and(|p: &Value| p.is_table()));
+
+    if !exists {
+        bail!(AttestError::Config(format!(
+            r#"the profile "{}" does not exist"#,
+            name
+        )));
+    }
+
+    set_item_toml("./attest.toml", "active_profile", Value::String(name))?;
+
+    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+
+    Ok(())
+}
+
+// Show profiles
+pub fn show_profiles() -> Result<()> {
+    let items: Map<String, Value> = items_toml("./attest.toml")?;
+
+    let active: Option<&str> = items.get("active_profile").and_then(|v: &Value| v.as_str());
+
+    let Some(profiles) = items.get("profile").and_then(|v: &Value| v.as_table()) else {
+        return Ok(());
+    };
+
+    for name in profiles.keys() {
+        if Some(name.as_str()) == active {
+            println!("{} {}", Marker::Plus, name);
+        } else {
+            println!("    {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn login(user_name: String, password: String) -> Result<()> {
     let url: &str = "https://atcoder.jp/login?continue=https://atcoder.jp/";
 
     let cookies: Arc<Jar> = Arc::new(Jar::default());
@@ -122,18 +448,9 @@ pub async fn login(user_name: String, password: String) {
     let client: Client = Client::builder()
         .cookie_store(true)
         .cookie_provider(cookies)
-        .build()
-        .unwrap_or_else(|e| {
-            panic!(
-                "{} something went wrong criating HTTPS Client. Error: {}",
-                Marker::Minus,
-                e
-            )
-        });
-
-    let page: Response = client.get(url).send().await.unwrap_or_else(|_| panic!("{}", Marker::minus("Url couldn't be parsed")));
+        .build()?;
 
-    let text: String = page.text().await.unwrap();
+    let text: String = request(&client, url).await?;
 
     let html: Html = Html::parse_document(&text);
 
@@ -142,9 +459,8 @@ pub async fn login(user_name: String, password: String) {
     let csrf_token: &str = html
         .select(&selector)
         .next()
-        .unwrap()
-        .attr("value")
-        .unwrap();
+        .and_then(|e: ElementRef| e.attr("value"))
+        .ok_or_else(|| AttestError::Scrape(String::from("the CSRF token was not found")))?;
 
     let mut form: HashMap<&str, &str> = HashMap::new();
 
@@ -152,45 +468,53 @@ pub async fn login(user_name: String, password: String) {
     form.insert("password", &password);
     form.insert("csrf_token", csrf_token);
 
-    let response: Response = client.post(url).form(&form).send().await.unwrap();
+    let response: Response = client
+        .post(url)
+        .form(&form)
+        .send()
+        .await
+        .with_context(|| AttestError::Network(format!("failed to connect to {}", url)))?;
+
+    if response.url().path() != "/" {
+        bail!(AttestError::Auth(String::from(
+            "Failed to login. The user name or password may be wrong"
+        )));
+    }
 
-    if response.url().path() == "/" {
-        let cookies: Vec<Cookie> = response.cookies().collect();
+    let cookies: Vec<Cookie> = response.cookies().collect();
 
-        let login_cookie: &Cookie = cookies
-            .iter()
-            .find(|&v: &&Cookie| v.name() == "REVEL_SESSION")
-            .unwrap();
+    let login_cookie: &Cookie = cookies
+        .iter()
+        .find(|&v: &&Cookie| v.name() == "REVEL_SESSION")
+        .ok_or_else(|| AttestError::Auth(String::from("the session cookie was not found")))?;
 
-        let cookie_value: &str = login_cookie.value();
+    let cookie_value: &str = login_cookie.value();
 
-        let mut dir: PathBuf = dirs::home_dir().unwrap();
+    let mut dir: PathBuf = global_dir()?;
 
-        dir.push(".attest_global");
+    if !dir.is_dir() {
+        create_dir_all(&dir).with_context(|| create_err(&dir))?;
+    }
 
-        if !dir.is_dir() {
-            create_dir_all(&dir).unwrap_or_else(|_| panic!("{}", create_err(&dir)));
-        }
+    dir.push("cookies.txt");
 
-        dir.push("cookies.txt");
+    let mut file: File = File::create(&dir).with_context(|| create_err(&dir))?;
 
-        let mut file: File = File::create(&dir).unwrap_or_else(|_| panic!("{}", create_err(&dir)));
+    writeln!(&mut file, "REVEL_SESSION = {}", cookie_value).with_context(|| write_err(&dir))?;
 
-        writeln!(&mut file, "REVEL_SESSION = {}", cookie_value)
-            .unwrap_or_else(|_| panic!("{}", write_err(dir)));
+    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
 
-        println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
-    } else {
-        println!("{}", Marker::minus("\x1b[31mFailed to login\x1b[m"));
-    }
+    Ok(())
 }
 
-pub fn logout() {
-    let mut dir: PathBuf = home_dir().unwrap();
+pub fn logout() -> Result<()> {
+    let dir: PathBuf = global_dir()?.join("cookies.txt");
 
-    dir.push(".attest_global/cookies.txt");
+    if dir.is_file() {
+        File::create(&dir).with_context(|| create_err(&dir))?;
+    }
 
-    File::create(&dir).unwrap_or_else(|_| panic!("{}", create_err(dir)));
+    Ok(())
 }
 
 pub async fn lang(
@@ -198,51 +522,48 @@ pub async fn lang(
     list: bool,
     url: Option<String>,
     search: Option<String>,
+    offline: bool,
+    global: bool,
 ) -> Result<()> {
     if lang.is_none() && !list && search.is_none() {
-        panic!("{} The lang command must have arguments", Marker::Minus);
+        bail!(AttestError::Usage(String::from(
+            "The lang command must have arguments"
+        )));
     }
 
-    let client: Client = make_client();
-
     let langs: Vec<(String, String)> = match url {
         Some(u) => {
             let url: String = u;
 
-            let text: String = request(&client, &url).await.unwrap();
-
-            let html: Html = to_html(text);
-
-            lang_select(&html)
-        }
-        None => {
-            let past_contests: String = request(
-                &client,
-                "https://atcoder.jp/contests/archive?ratedType=1&category=0&keyword=",
-            )
-            .await?;
-
-            let html: Html = to_html(past_contests);
-
-            let selector: Selector =
-                Selector::parse(r#"div[class="table-responsive"] tbody td span + a"#).unwrap();
-
-            let url_elem: ElementRef = html.select(&selector).next().unwrap();
+            let client: Client = make_client();
 
-            let url: String =
-                String::from("https://atcoder.jp") + url_elem.attr("href").unwrap() + "/submit";
-
-            let text: String = request(&client, &url).await.unwrap();
+            let text: String = request(&client, &url)
+                .await
+                .context("Failed to connect to AtCoder. The --url option needs the network")?;
 
             let html: Html = to_html(text);
 
-            let selector: Selector =
-                Selector::parse(r#"label[for="select-lang"] + div select"#).unwrap();
-
-            let selected: Html = to_html(html.select(&selector).next().unwrap().html());
-
-            lang_select(&selected)
+            lang_select(&html)?
         }
+        None if offline => lang_cache().ok_or_else(|| {
+            AttestError::Network(String::from(
+                "No cached langs. Please run without --offline once",
+            ))
+        })?,
+        None => match latest_langs().await {
+            Ok(langs) => {
+                save_lang_cache(&langs)?;
+                langs
+            }
+            Err(e) => {
+                eprintln!("{} Failed to download langs: {}", Marker::Minus, e);
+                lang_cache().ok_or_else(|| {
+                    AttestError::Network(String::from(
+                        "No cached langs. Please connect to the network",
+                    ))
+                })?
+            }
+        },
     };
 
     if let Some(lang_name) = search {
@@ -262,12 +583,20 @@ pub async fn lang(
         let lang_code: &str = &langs
             .iter()
             .find(|&v: &&(String, String)| v.0 == lang_name)
-            .unwrap_or_else(|| panic!("{}", Marker::minus("The lang cannot be used")))
+            .ok_or_else(|| {
+                AttestError::Usage(format!(r#"the lang "{}" cannot be used"#, lang_name))
+            })?
             .1;
-        set_item_toml("./attest.toml", "lang", Value::String(lang_code.to_owned()));
+        set_item_toml(
+            settings_path(global)?,
+            "lang",
+            Value::String(lang_code.to_owned()),
+        )?;
         println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
     } else {
-        panic!("{}", Marker::minus("Arguments may be wrong format"));
+        bail!(AttestError::Usage(String::from(
+            "Arguments may be wrong format"
+        )));
     }
 
     Ok(())
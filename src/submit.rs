@@ -1,26 +1,265 @@
 use std::collections::HashMap;
+use std::io::{stdout, Write};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
+use crate::cache::task_cache;
+use crate::cargo::apply_cargo;
+use crate::config::{report_warnings, settings, Config, Problem};
+use crate::error::AttestError;
+use crate::task::{contest_from_url, resolve_file_path, Task};
 use crate::utils::{
-    file_read_to_string, get_item_toml, lang_select, link_from_copy, make_client, request, to_html,
-    Marker,
+    file_read_to_string, lang_select, link_from_copy, make_client, request, to_html, Marker,
 };
 
-use reqwest::Client;
+use anyhow::{bail, Context, Result};
 
-use toml::Value;
+use regex::Regex;
 
-use scraper::{Html, Selector};
+use reqwest::{Client, Response};
 
-// Submit Code
-pub async fn submit(url: Option<String>, lang: Option<String>) {
+use tokio::time;
+
+use scraper::{ElementRef, Html, Selector};
+
+const NETWORK_ERR: &str = "Failed to connect to AtCoder. Submitting needs the network";
+
+// Language to submit the code in
+#[derive(Debug, Clone)]
+pub enum Lang {
+    // ID of the language in the submit form
+    Code(String),
+    // Name of the language like "Python (CPython 3.11.4)"
+    Name(String),
+}
+
+// Seconds between the requests for the judge status
+const POLL_INTERVAL: u64 = 2;
+
+// Seconds to wait for the verdict
+const POLL_TIMEOUT: u64 = 600;
+
+// Judge status of the submission
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    // Verdict like "AC", or progress like "12/34 WJ" while judging
+    pub status: String,
+    pub score: Option<String>,
+    pub time: Option<String>,
+    pub memory: Option<String>,
+}
+
+impl Verdict {
+    // Whether the judge finished
+    pub fn is_final(&self) -> bool {
+        !JUDGING.is_match(self.status.trim())
+    }
+}
+
+// Status while judging like "12/34 WJ", "WJ" or "Judging"
+static JUDGING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\d+\s*/\s*\d+|^(WJ|WR)$|Judging").unwrap());
+
+// Submit Code, and wait for the verdict if `wait` is true
+pub async fn submit(
+    url: Option<String>,
+    lang: Option<String>,
+    profile: Option<String>,
+    wait: bool,
+) -> Result<()> {
     let url: String = match url {
         Some(s) => s,
         None => link_from_copy(),
     };
 
+    if let Some(cache) = Task::from_url(&url).and_then(|t: Task| task_cache(&t)) {
+        println!("{} Submitting to {}", Marker::X, cache.meta.title);
+    }
+
+    let (mut config, warnings): (Config, Vec<Problem>) = settings(profile.as_deref())?;
+
+    report_warnings(&warnings);
+
+    resolve_file_path(&mut config, &url)?;
+
+    apply_cargo(&mut config, Task::from_url(&url).as_ref())?;
+
+    let lang: Lang = match lang {
+        Some(lang_name) => Lang::Name(lang_name),
+        None => Lang::Code(config.lang.clone().ok_or_else(|| {
+            AttestError::Config(String::from(
+                r#"You have to set lang by `attest lang <LANG>` or "lang" value"#,
+            ))
+        })?),
+    };
+
+    let file_path: &str = config.file_path().ok_or_else(|| {
+        AttestError::Config(String::from(
+            "You have to set file path by `attest set file <FILE_PATH>`",
+        ))
+    })?;
+
+    let code: String = file_read_to_string(file_path)?;
+
     let client: Client = make_client();
 
-    let s: String = request(&client, &url).await.unwrap();
+    let id: u64 = submit_code(&client, &url, &lang, &code).await?;
+
+    let contest: String = contest_from_url(&url)
+        .ok_or_else(|| AttestError::Usage(format!("the contest of {} was not found", url)))?;
+
+    println!(
+        "{} https://atcoder.jp/contests/{}/submissions/{}",
+        Marker::X,
+        contest,
+        id
+    );
+
+    if !wait {
+        println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+        return Ok(());
+    }
+
+    let verdict: Verdict = wait_verdict(&client, &contest, id, |v: &Verdict| {
+        print!("\r\x1b[K{} {}", Marker::X, v.status);
+        let _ = stdout().flush();
+    })
+    .await?;
+
+    print!("\r\x1b[K");
+
+    println!("{}", render_verdict(&verdict));
+
+    Ok(())
+}
+
+// Show the verdict with the score, time and memory
+fn render_verdict(verdict: &Verdict) -> String {
+    let mut buf: Vec<String> = vec![if verdict.status == "AC" {
+        Marker::plus("\x1b[32mAC\x1b[m")
+    } else {
+        Marker::minus(format!("\x1b[33m{}\x1b[m", verdict.status))
+    }];
+
+    for (name, value) in [
+        ("score", &verdict.score),
+        ("time", &verdict.time),
+        ("memory", &verdict.memory),
+    ] {
+        if let Some(value) = value {
+            buf.push(format!("{}: {}", name, value));
+        }
+    }
+
+    buf.join("  ")
+}
+
+// Get the judge status until the judge finishes, calling `on_progress` while judging
+pub async fn wait_verdict<F: FnMut(&Verdict)>(
+    client: &Client,
+    contest: &str,
+    id: u64,
+    mut on_progress: F,
+) -> Result<Verdict> {
+    let start: Instant = Instant::now();
+
+    loop {
+        let verdict: Verdict = judge_status(client, contest, id).await?;
+
+        if verdict.is_final() {
+            return Ok(verdict);
+        }
+
+        on_progress(&verdict);
+
+        if start.elapsed() > Duration::from_secs(POLL_TIMEOUT) {
+            bail!(AttestError::Network(format!(
+                "the judge didn't finish in {} seconds",
+                POLL_TIMEOUT
+            )));
+        }
+
+        time::sleep(Duration::from_secs(POLL_INTERVAL)).await;
+    }
+}
+
+// Get the judge status of the submission
+pub async fn judge_status(client: &Client, contest: &str, id: u64) -> Result<Verdict> {
+    let url: String = format!(
+        "https://atcoder.jp/contests/{}/submissions/{}?lang=en",
+        contest, id
+    );
+
+    let s: String = request(client, &url).await.context(NETWORK_ERR)?;
+
+    parse_verdict(&to_html(s)).ok_or_else(|| {
+        AttestError::Scrape(format!("the judge status was not found in {}", url)).into()
+    })
+}
+
+// Read the judge status from the page of the submission
+pub fn parse_verdict(html: &Html) -> Option<Verdict> {
+    let status_selector: Selector = Selector::parse("#judge-status").unwrap();
+
+    let status: String = html
+        .select(&status_selector)
+        .next()?
+        .text()
+        .collect::<String>()
+        .trim()
+        .to_string();
+
+    let row_selector: Selector = Selector::parse("tr").unwrap();
+    let th_selector: Selector = Selector::parse("th").unwrap();
+    let td_selector: Selector = Selector::parse("td").unwrap();
+
+    let rows: HashMap<String, String> = html
+        .select(&row_selector)
+        .filter_map(|row: ElementRef| {
+            let th: ElementRef = row.select(&th_selector).next()?;
+            let td: ElementRef = row.select(&td_selector).next()?;
+
+            Some((
+                th.text().collect::<String>().trim().to_string(),
+                td.text().collect::<String>().trim().to_string(),
+            ))
+        })
+        .collect();
+
+    Some(Verdict {
+        status,
+        score: rows.get("Score").cloned(),
+        time: rows.get("Exec Time").cloned(),
+        memory: rows.get("Memory").cloned(),
+    })
+}
+
+// Find the newest submission to the task in "My Submissions", which has the largest ID
+fn find_submission_id(html: &Html, task_screen_name: &str) -> Option<u64> {
+    let row_selector: Selector = Selector::parse("tr").unwrap();
+
+    let link_selector: Selector = Selector::parse("a[href]").unwrap();
+
+    let re: Regex = Regex::new(r"^/contests/[^/]+/submissions/(\d+)$").unwrap();
+
+    let task_link: String = format!("/tasks/{}", task_screen_name);
+
+    html.select(&row_selector)
+        .filter(|row: &ElementRef| {
+            row.select(&link_selector).any(|a: ElementRef| {
+                a.attr("href")
+                    .is_some_and(|h: &str| h.ends_with(&task_link))
+            })
+        })
+        .flat_map(|row: ElementRef| row.select(&link_selector))
+        .filter_map(|a: ElementRef| re.captures(a.attr("href")?))
+        .filter_map(|c: regex::Captures| c[1].parse().ok())
+        .max()
+}
+
+// Submit the code to the task of the URL, and get the ID of the submission
+pub async fn submit_code(client: &Client, url: &str, lang: &Lang, code: &str) -> Result<u64> {
+    let s: String = request(client, url).await.context(NETWORK_ERR)?;
 
     let html: Html = to_html(s);
 
@@ -32,9 +271,8 @@ pub async fn submit(url: Option<String>, lang: Option<String>) {
     let task_screen_name: &str = html
         .select(&task_screen_name_selrctor)
         .next()
-        .unwrap_or_else(|| panic!("{}", Marker::minus("You may not login")))
-        .attr("value")
-        .unwrap();
+        .and_then(|e: ElementRef| e.attr("value"))
+        .ok_or_else(|| AttestError::Auth(String::from("You may not login")))?;
 
     form.insert("data.TaskScreenName", task_screen_name);
 
@@ -43,45 +281,29 @@ pub async fn submit(url: Option<String>, lang: Option<String>) {
     let csrf_token: &str = html
         .select(&csrf_token_selector)
         .next()
-        .unwrap()
-        .attr("value")
-        .unwrap();
+        .and_then(|e: ElementRef| e.attr("value"))
+        .ok_or_else(|| AttestError::Scrape(String::from("the CSRF token was not found")))?;
 
     form.insert("csrf_token", csrf_token);
 
     let lang_code: String = match lang {
-        None => get_item_toml("./attest.toml", "lang")
-            .unwrap_or_else(|| panic!("{}", Marker::minus("You have to set lang")))
-            .as_str()
-            .unwrap()
-            .to_owned(),
-        Some(lang_name) => {
-            let text: String = request(&client, &url).await.unwrap();
-
-            let html: Html = to_html(text);
+        Lang::Code(code) => code.clone(),
+        Lang::Name(lang_name) => {
+            let langs: Vec<(String, String)> = lang_select(&html)?;
 
-            let langs: Vec<(String, String)> = lang_select(&html);
-
-            let lang_code: &str = &langs
-                .iter()
-                .find(|&v: &&(String, String)| v.0 == lang_name)
-                .unwrap_or_else(|| panic!("{}", Marker::minus("The lang cannot be used")))
-                .1;
-
-            lang_code.to_string()
+            langs
+                .into_iter()
+                .find(|v: &(String, String)| &v.0 == lang_name)
+                .ok_or_else(|| {
+                    AttestError::Usage(format!(r#"the lang "{}" cannot be used"#, lang_name))
+                })?
+                .1
         }
     };
 
     form.insert("data.LanguageId", &lang_code);
 
-    let file_path_string: Value = get_item_toml("./attest.toml", "file_path")
-        .unwrap_or_else(|| panic!("{}", Marker::minus("You have to set file path")));
-
-    let file_path: &str = file_path_string.as_str().unwrap();
-
-    let code: String = file_read_to_string(file_path);
-
-    form.insert("sourceCode", &code);
+    form.insert("sourceCode", code);
 
     let require_addr_selector: Selector =
         Selector::parse(r#"form[class="form-horizontal form-code-submit"]"#).unwrap();
@@ -89,13 +311,131 @@ pub async fn submit(url: Option<String>, lang: Option<String>) {
     let require_addr: &str = html
         .select(&require_addr_selector)
         .next()
-        .unwrap()
-        .attr("action")
-        .unwrap();
+        .and_then(|e: ElementRef| e.attr("action"))
+        .ok_or_else(|| AttestError::Scrape(String::from("the submit form was not found")))?;
 
     let addr: String = String::from("https://atcoder.jp") + require_addr;
 
-    client.post(&addr).form(&form).send().await.unwrap();
+    let response: Response = client
+        .post(&addr)
+        .form(&form)
+        .send()
+        .await
+        .with_context(|| AttestError::Network(String::from(NETWORK_ERR)))?;
+
+    // AtCoder redirects to "My Submissions" if the submission is accepted
+    let redirected: bool = response.url().path().ends_with("/submissions/me");
+
+    let text: String = response
+        .text()
+        .await
+        .with_context(|| AttestError::Network(String::from(NETWORK_ERR)))?;
+
+    let html: Html = to_html(text);
+
+    if !redirected {
+        let alert_selector: Selector = Selector::parse(".alert-danger").unwrap();
+
+        let message: String = html
+            .select(&alert_selector)
+            .next()
+            .map(|e: ElementRef| e.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+
+        bail!(AttestError::Usage(format!(
+            "AtCoder rejected the submission. {}",
+            message.trim_start_matches('×').trim()
+        )));
+    }
+
+    find_submission_id(&html, task_screen_name).ok_or_else(|| {
+        AttestError::Scrape(String::from(
+            "the submission was not found in \"My Submissions\"",
+        ))
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verdict(status: &str) -> Verdict {
+        Verdict {
+            status: status.to_string(),
+            score: None,
+            time: None,
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn verdict_is_parsed() {
+        let html: Html = Html::parse_document(
+            r#"<table>
+<tr><th>Task</th><td>A - Test</td></tr>
+<tr><th>Score</th><td>100</td></tr>
+<tr><th>Status</th><td id="judge-status"><span> AC </span></td></tr>
+<tr><th>Exec Time</th><td>12 ms</td></tr>
+<tr><th>Memory</th><td>3840 KB</td></tr>
+</table>"#,
+        );
+
+        assert_eq!(
+            parse_verdict(&html),
+            Some(Verdict {
+                status: String::from("AC"),
+                score: Some(String::from("100")),
+                time: Some(String::from("12 ms")),
+                memory: Some(String::from("3840 KB")),
+            })
+        );
+    }
+
+    #[test]
+    fn verdict_while_judging_has_no_time() {
+        let html: Html = Html::parse_document(
+            r#"<table><tr><th>Status</th><td id="judge-status">3/12 WJ</td></tr></table>"#,
+        );
+
+        let judging: Verdict = parse_verdict(&html).unwrap();
+
+        assert_eq!(judging.status, "3/12 WJ");
+        assert_eq!(judging.time, None);
+        assert!(!judging.is_final());
+    }
+
+    #[test]
+    fn page_without_status_has_no_verdict() {
+        assert_eq!(
+            parse_verdict(&Html::parse_document("<p>Not found</p>")),
+            None
+        );
+    }
+
+    #[test]
+    fn final_verdicts() {
+        for status in ["AC", "WA", "TLE", "CE", "RE"] {
+            assert!(verdict(status).is_final(), "{}", status);
+        }
+
+        for status in ["WJ", "WR", "Judging", "3 / 12", "5/5 TLE"] {
+            assert!(!verdict(status).is_final(), "{}", status);
+        }
+    }
+
+    #[test]
+    fn newest_submission_to_the_task() {
+        let html: Html = Html::parse_document(
+            r#"<table>
+<tr><td><a href="/contests/abc300/tasks/abc300_b">B</a></td><td><a href="/contests/abc300/submissions/30">Detail</a></td></tr>
+<tr><td><a href="/contests/abc300/tasks/abc300_a">A</a></td><td><a href="/contests/abc300/submissions/42">Detail</a></td></tr>
+<tr><td><a href="/contests/abc300/tasks/abc300_a">A</a></td><td><a href="/contests/abc300/submissions/41">Detail</a></td></tr>
+</table>"#,
+        );
 
-    println!("{}", Marker::plus("\x1b[32mFinished successfully\x1b[m"));
+        assert_eq!(find_submission_id(&html, "abc300_a"), Some(42));
+        assert_eq!(find_submission_id(&html, "abc300_b"), Some(30));
+        assert_eq!(find_submission_id(&html, "abc300_c"), None);
+    }
 }
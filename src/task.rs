@@ -0,0 +1,446 @@
+use std::env;
+use std::ffi::OsStr;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+
+use regex::Regex;
+
+use reqwest::Client;
+
+use scraper::{ElementRef, Html, Selector};
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{current_contest, save_task_list, task_list_cache};
+use crate::config::{Build, BuildStep, Config, FilePath};
+use crate::error::AttestError;
+use crate::utils::{create_err, make_client, request, to_html};
+
+// Path of the task in the URL
+static TASK_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/contests/([^/?#]+)/tasks/([^/?#]+)").unwrap());
+
+// Path of the contest in the URL
+static CONTEST_URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"/contests/([^/?#]+)").unwrap());
+
+// Task of AtCoder
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub contest: String,
+    pub id: String,
+}
+
+impl Task {
+    // Get the task from the URL like `https://atcoder.jp/contests/abc300/tasks/abc300_a`
+    pub fn from_url(url: &str) -> Option<Self> {
+        let captures = TASK_URL.captures(url)?;
+
+        Some(Task {
+            contest: captures[1].to_string(),
+            id: captures[2].to_string(),
+        })
+    }
+
+    // URL of the task page
+    pub fn url(&self) -> String {
+        format!(
+            "https://atcoder.jp/contests/{}/tasks/{}",
+            self.contest, self.id
+        )
+    }
+}
+
+// Get the contest ID from the URL like `https://atcoder.jp/contests/abc300`
+pub fn contest_from_url(url: &str) -> Option<String> {
+    Some(CONTEST_URL.captures(url)?[1].to_string())
+}
+
+// Get tasks of the contest with their letters like `A`
+pub async fn task_list(contest: &str) -> Result<Vec<(String, Task)>> {
+    if let Some(list) = task_list_cache(contest) {
+        return Ok(list);
+    }
+
+    let client: Client = make_client();
+
+    let text: String = request(
+        &client,
+        &format!("https://atcoder.jp/contests/{}/tasks", contest),
+    )
+    .await?;
+
+    let html: Html = to_html(text);
+
+    let selector: Selector = Selector::parse(r#"table tbody tr td:first-child a"#).unwrap();
+
+    let list: Vec<(String, Task)> = html
+        .select(&selector)
+        .filter_map(|a: ElementRef| {
+            let letter: String = a.text().collect::<String>().trim().to_string();
+            Some((letter, Task::from_url(a.attr("href")?)?))
+        })
+        .collect();
+
+    // The list is empty before the contest starts, so it should be scraped again later
+    if !list.is_empty() {
+        save_task_list(contest, &list)?;
+    }
+
+    Ok(list)
+}
+
+// Resolve the task alias like `c` to the URL of the task in the current contest
+pub async fn resolve_alias(url: Option<String>) -> Result<Option<String>> {
+    let Some(alias) = url else {
+        return Ok(None);
+    };
+
+    if alias.contains("://") {
+        return Ok(Some(alias));
+    }
+
+    let contest: String = current_contest().ok_or_else(|| {
+        AttestError::Usage(String::from(
+            "Please set the contest by `attest contest <CONTEST>` to use task aliases",
+        ))
+    })?;
+
+    let list: Vec<(String, Task)> = task_list(&contest).await?;
+
+    let (_, task) = list
+        .iter()
+        .find(|(letter, task): &&(String, Task)| {
+            letter.eq_ignore_ascii_case(&alias) || task.id == alias
+        })
+        .ok_or_else(|| {
+            AttestError::Usage(format!(
+                r#"the task "{}" does not exist in {}"#,
+                alias, contest
+            ))
+        })?;
+
+    Ok(Some(task.url()))
+}
+
+// Resolve the program file of the task in the settings
+//
+// `file_path` can be a path with `{task}` and `{contest}`, or a table from task IDs to paths
+pub fn resolve_file_path(config: &mut Config, url: &str) -> Result<()> {
+    let Some(file_path) = &config.file_path else {
+        return Ok(());
+    };
+
+    let task: Option<Task> = Task::from_url(url);
+
+    let no_task = || {
+        AttestError::Usage(String::from(
+            "Please input the URL of the task to find the program file",
+        ))
+    };
+
+    let resolved: String = match file_path {
+        FilePath::Path(s) if s.contains("{task}") || s.contains("{contest}") => {
+            let task: &Task = task.as_ref().ok_or_else(no_task)?;
+
+            s.replace("{task}", &task.id)
+                .replace("{contest}", &task.contest)
+        }
+        FilePath::Path(_) => return Ok(()),
+        FilePath::Tasks(t) => {
+            let task: &Task = task.as_ref().ok_or_else(no_task)?;
+
+            t.get(&task.id)
+                .ok_or_else(|| {
+                    AttestError::Config(format!(
+                        r#"the "file_path" value has no file for "{}""#,
+                        task.id
+                    ))
+                })?
+                .clone()
+        }
+    };
+
+    config.file_path = Some(FilePath::Path(resolved));
+
+    Ok(())
+}
+
+// Expand the placeholders and the environment variables in the build, run and test commands
+//
+// `{file}`, `{file_stem}` and `{dir}` come from the program file, `{task}` and `{contest}` from the task,
+// and `{tmp}` is `.attest/tmp` in the project root.
+// With `shell = true`, each command runs through `sh -c`. The shell expands the environment variables
+// in a script of one string, and attest expands them in a command of more strings before quoting
+pub fn expand_commands(config: &mut Config, task: Option<&Task>) -> Result<()> {
+    let file: Option<&Path> = config.file_path().map(Path::new);
+
+    let tmp: PathBuf = env::current_dir()?.join(".attest/tmp");
+
+    let values: [(&str, Option<String>); 6] = [
+        (
+            "file",
+            file.map(|f: &Path| f.to_string_lossy().into_owned()),
+        ),
+        (
+            "file_stem",
+            file.and_then(Path::file_stem)
+                .map(|s: &OsStr| s.to_string_lossy().into_owned()),
+        ),
+        (
+            "dir",
+            file.and_then(Path::parent)
+                .map(|d: &Path| match d.to_string_lossy().into_owned() {
+                    d if d.is_empty() => String::from("."),
+                    d => d,
+                }),
+        ),
+        ("task", task.map(|t: &Task| t.id.clone())),
+        ("contest", task.map(|t: &Task| t.contest.clone())),
+        ("tmp", Some(tmp.to_string_lossy().into_owned())),
+    ];
+
+    let uses_tmp: bool = config
+        .build
+        .commands_mut()
+        .into_iter()
+        .flatten()
+        .chain(config.run.iter_mut())
+        .chain(config.test.iter_mut().flatten())
+        .any(|arg: &mut String| arg.contains("{tmp}"));
+
+    if uses_tmp {
+        create_dir_all(&tmp).with_context(|| create_err(&tmp))?;
+    }
+
+    let shell: bool = config.shell;
+
+    // Values put in a script for the shell are quoted, so that paths with spaces stay one word
+    let quoted: Vec<(&str, Option<String>)> = values
+        .iter()
+        .map(|(name, value): &(&str, Option<String>)| {
+            (
+                *name,
+                value
+                    .as_deref()
+                    .map(|v: &str| shell_words::quote(v).into_owned()),
+            )
+        })
+        .collect();
+
+    let expand = |command: &mut Vec<String>| -> Result<()> {
+        if !shell {
+            for arg in command.iter_mut() {
+                *arg = expand_env(&expand_placeholders(arg, &values)?)?;
+            }
+
+            return Ok(());
+        }
+
+        // One string is a script for the shell, and more strings are the arguments of one command,
+        // whose environment variables are expanded before quoting them
+        let script: String = match command.as_slice() {
+            [] => return Ok(()),
+            [script] => expand_placeholders(script, &quoted)?,
+            args => shell_words::join(
+                args.iter()
+                    .map(|arg: &String| expand_env(&expand_placeholders(arg, &values)?))
+                    .collect::<Result<Vec<String>>>()?,
+            ),
+        };
+
+        // `"$@"` passes the arguments added by attest like the output to the test command
+        *command = vec![
+            String::from("sh"),
+            String::from("-c"),
+            script + r#" "$@""#,
+            String::from("sh"),
+        ];
+
+        Ok(())
+    };
+
+    for command in config.build.commands_mut() {
+        expand(command)?;
+    }
+
+    if let Build::Steps(steps) = &mut config.build {
+        for path in steps
+            .iter_mut()
+            .flat_map(|s: &mut BuildStep| s.inputs.iter_mut().chain(s.outputs.iter_mut()))
+        {
+            *path = expand_placeholders(path, &values)?;
+        }
+    }
+
+    expand(&mut config.run)?;
+
+    if let Some(test) = config.test.as_mut() {
+        expand(test)?;
+    }
+
+    Ok(())
+}
+
+// Replace `{name}` with its value, and leave unknown names like `{print $1}` as they are
+fn expand_placeholders(arg: &str, values: &[(&str, Option<String>)]) -> Result<String> {
+    let mut arg: String = arg.to_string();
+
+    for (name, value) in values {
+        let placeholder: String = format!("{{{}}}", name);
+
+        if !arg.contains(&placeholder) {
+            continue;
+        }
+
+        let value: &String = value.as_ref().ok_or_else(|| match *name {
+            "task" | "contest" => AttestError::Usage(format!(
+                "Please input the URL of the task to expand {}",
+                placeholder
+            )),
+            _ => AttestError::Config(format!(
+                "You have to set file path to expand {} by `attest set file <FILE_PATH>`",
+                placeholder
+            )),
+        })?;
+
+        arg = arg.replace(&placeholder, value);
+    }
+
+    Ok(arg)
+}
+
+// Reference to an environment variable like `$NAME` or `${NAME}`, or `$$`
+static ENV_VAR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$(?:\$|\{([A-Za-z_][A-Za-z0-9_]*)\}|([A-Za-z_][A-Za-z0-9_]*))").unwrap()
+});
+
+// Replace `$NAME` and `${NAME}` with the environment variables, and `$$` with `$`
+//
+// `$NAME` of an unset variable is left as it is like `$NF` of awk, while `${NAME}` must be set
+fn expand_env(arg: &str) -> Result<String> {
+    let mut expanded: String = String::new();
+
+    let mut last: usize = 0;
+
+    for caps in ENV_VAR.captures_iter(arg) {
+        let whole: regex::Match = caps.get(0).unwrap();
+
+        expanded.push_str(&arg[last..whole.start()]);
+
+        match (caps.get(1), caps.get(2)) {
+            (Some(name), _) => {
+                let value: String = env::var(name.as_str()).map_err(|_| {
+                    AttestError::Config(format!(
+                        "the environment variable {} is not set",
+                        name.as_str()
+                    ))
+                })?;
+
+                expanded.push_str(&value);
+            }
+            (None, Some(name)) => match env::var(name.as_str()) {
+                Ok(value) => expanded.push_str(&value),
+                Err(_) => expanded.push_str(whole.as_str()),
+            },
+            (None, None) => expanded.push('$'),
+        }
+
+        last = whole.end();
+    }
+
+    expanded.push_str(&arg[last..]);
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("file", Some(String::from("abc300/abc300_a.cpp"))),
+            ("task", Some(String::from("abc300_a"))),
+            ("contest", None),
+        ]
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        assert_eq!(
+            expand_placeholders("{task}.out", &values()).unwrap(),
+            "abc300_a.out"
+        );
+        assert_eq!(
+            expand_placeholders("--file={file}", &values()).unwrap(),
+            "--file=abc300/abc300_a.cpp"
+        );
+    }
+
+    #[test]
+    fn unknown_braces_are_kept() {
+        assert_eq!(
+            expand_placeholders("{print $1}", &values()).unwrap(),
+            "{print $1}"
+        );
+    }
+
+    #[test]
+    fn placeholders_without_values_are_errors() {
+        assert!(expand_placeholders("{contest}", &values()).is_err());
+    }
+
+    #[test]
+    fn env_is_expanded() {
+        env::set_var("ATTEST_TEST_NAME", "value");
+
+        assert_eq!(expand_env("$ATTEST_TEST_NAME/a").unwrap(), "value/a");
+        assert_eq!(expand_env("${ATTEST_TEST_NAME}_a").unwrap(), "value_a");
+        assert_eq!(
+            expand_env("$$ATTEST_TEST_NAME").unwrap(),
+            "$ATTEST_TEST_NAME"
+        );
+    }
+
+    #[test]
+    fn env_is_expanded_before_quoting_arguments_for_shell() {
+        env::set_var("ATTEST_TEST_SHELL", "a b");
+
+        let mut config: Config = Config::from_items(
+            r#"
+shell = true
+run = ["echo", "$ATTEST_TEST_SHELL", "{task}"]
+test = ["echo $ATTEST_TEST_SHELL {task}"]
+"#
+            .parse::<toml::Table>()
+            .unwrap(),
+        )
+        .unwrap();
+
+        let task: Task = Task {
+            contest: String::from("abc300"),
+            id: String::from("abc300_a"),
+        };
+
+        expand_commands(&mut config, Some(&task)).unwrap();
+
+        assert_eq!(config.run[2], r#"echo 'a b' abc300_a "$@""#);
+        assert_eq!(
+            config.test.unwrap()[2],
+            r#"echo $ATTEST_TEST_SHELL abc300_a "$@""#
+        );
+    }
+
+    #[test]
+    fn unset_env_is_kept_unless_braced() {
+        assert_eq!(
+            expand_env("awk '{print $ATTEST_TEST_UNSET}'").unwrap(),
+            "awk '{print $ATTEST_TEST_UNSET}'"
+        );
+        assert_eq!(expand_env("$1").unwrap(), "$1");
+        assert!(expand_env("${ATTEST_TEST_UNSET}").is_err());
+    }
+}
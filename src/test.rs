@@ -1,25 +1,28 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     env::current_dir,
     fmt::Write as _,
     fs::{self, File},
     future::Future,
     io::Write,
-    num::IntErrorKind,
     path::{Path, PathBuf},
-    process::{Command as StdCommand, Output, Stdio},
-    sync::Arc,
+    process::{ExitStatus, Output, Stdio},
+    sync::{Arc, LazyLock},
     time::{Duration, Instant},
 };
 
-use crate::utils::{
-    create_err, file_read_to_string, hash_from, items_toml, link_from_copy, make_client, request,
-    to_html, write_err, Marker,
+use crate::cache::{
+    current_task, global_task_cache, global_task_dir, old_task_cache, restore_artifact,
+    save_artifact, save_current_task, save_task_cache, save_task_cache_in, task_cache, TaskCache,
 };
+use crate::cargo::{apply_cargo, strip_status};
+use crate::config::{report_warnings, settings, Build, BuildStep, Config, Problem};
+use crate::error::AttestError;
+use crate::problem::{download_task, ProblemMeta};
+use crate::task::{expand_commands, resolve_file_path, Task};
+use crate::utils::{create_err, hash_from, make_client, write_err, Marker};
 
-use anyhow::{bail, Result};
-
-use reqwest::Client;
+use anyhow::{bail, Context, Result};
 
 use tokio::{
     io::AsyncWriteExt,
@@ -27,104 +30,177 @@ use tokio::{
     time,
 };
 
-use toml::{map::Map, Value};
-
 use serde::{Deserialize, Serialize};
 
-use scraper::{Html, Selector};
+use proconio_derive::fastout;
 
 use regex::Regex;
 
-use proconio_derive::fastout;
-
 // Function to test
 pub async fn test(
     url: Option<String>,
     example_num: Vec<usize>,
     p_build: bool,
+    profile: Option<String>,
+    offline: bool,
+    refresh: bool,
+    show_warnings: bool,
 ) -> Result<Option<Vec<Option<Res>>>> {
-    let (examples, time_limit): (Vec<IO>, u128);
+    let task: Task = match url {
+        Some(u) => Task::from_url(&u),
+        None => current_task(),
+    }
+    .ok_or_else(|| AttestError::Usage(String::from("Please input the URL of the task")))?;
 
-    if url.is_none() || is_same_link(url.as_ref().unwrap()) {
-        examples = examples_from_cache();
+    let (mut config, warnings): (Config, Vec<Problem>) = settings(profile.as_deref())?;
 
-        time_limit = time_limit_from_cache();
-    } else {
-        let url: String = url.unwrap();
+    report_warnings(&warnings);
 
-        let c: Client = make_client();
+    let cache: TaskCache = load_task(&task, &config, offline, refresh).await?;
 
-        let text: String = request(&c, &url).await?;
+    save_current_task(&task)?;
+
+    if !cache.meta.title.is_empty() {
+        println!("{} \x1b[35m{}\x1b[m\n", Marker::X, cache.meta.title);
+    }
 
-        let html: Html = to_html(text);
+    resolve_file_path(&mut config, &task.url())?;
 
-        examples = packing_to_io(&select_samples(&html));
+    apply_cargo(&mut config, Some(&task))?;
 
-        time_limit = get_time_limit(&html);
+    expand_commands(&mut config, Some(&task))?;
 
-        save_cache(&url, time_limit, &examples);
+    let has_test_command: bool = config.test.is_some();
+
+    if let Some(tolerance) = cache.meta.tolerance.filter(|_| !has_test_command) {
+        println!(
+            "{} Judging with absolute or relative error {:e}",
+            Marker::X,
+            tolerance
+        );
     }
-    let setting_toml: Map<String, Value> = items_toml("./attest.toml");
 
-    let results: Option<Vec<Option<Res>>> =
-        tester(&examples, &setting_toml, time_limit, example_num, p_build).await;
+    if cache.meta.multiple_answers && !has_test_command {
+        eprintln!(
+            "{}",
+            Marker::minus(
+                "\x1b[33mThis task may accept multiple answers.\x1b[m \
+                Outputs different from the examples are shown as WA?. \
+                Please set the test command to judge them"
+            )
+        );
+    }
 
-    Ok(results)
-}
+    if cache.time_limit == u128::MAX {
+        eprintln!(
+            "{}",
+            Marker::minus("Failed to get the time limit, so TLE is not judged")
+        );
+    }
 
-// Check if the link is same
-fn is_same_link(url: &str) -> bool {
-    url == link_from_copy()
-}
+    let dir: PathBuf = current_dir()?;
 
-// Get examples from cache if the link is same
-fn examples_from_cache() -> Vec<IO> {
-    let text: String = file_read_to_string("./.attest/examples.json");
+    if let Some(ce) = build_wrap(&config, &dir, p_build, show_warnings).await? {
+        println!("{}", ce);
+        return Ok(None);
+    }
 
-    serde_json::from_str(text.trim()).unwrap()
-}
+    let test_config: TestConfig = TestConfig::from_settings(&config, &dir)?;
 
-// Get time limit from cache if the link is same
-fn time_limit_from_cache() -> u128 {
-    let time: String = file_read_to_string("./.attest/time_limit.txt");
+    let results: Vec<Result<ExampleResult>> = tester(
+        &cache.examples,
+        &test_config,
+        cache.time_limit,
+        &example_num,
+        &cache.meta,
+    )
+    .await;
 
-    time.trim().parse().unwrap()
-}
+    print_results(&results);
 
-// Save cache if the link is different
-fn save_cache(url: &str, time_limit: u128, examples: &Vec<IO>) {
-    let mut l: File = File::create("./.attest/url.txt")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/url.txt")));
-    write!(&mut l, "{}", url).unwrap_or_else(|_| panic!("{}", write_err("./.attest/url.txt")));
-
-    let mut t: File = File::create("./.attest/time_limit.txt")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/time_limit.txt")));
-    write!(&mut t, "{}", time_limit)
-        .unwrap_or_else(|_| panic!("{}", write_err("./.attest/time_limit.txt")));
-
-    let mut e: File = File::create("./.attest/examples.json")
-        .unwrap_or_else(|_| panic!("{}", create_err("./.attest/examples.json")));
-    write!(&mut e, "{}", serde_json::to_string(examples).unwrap())
-        .unwrap_or_else(|_| panic!("{}", write_err("./.attest/examples.json")));
+    Ok(Some(
+        results
+            .iter()
+            .map(|r: &Result<ExampleResult>| r.as_ref().ok().map(|e: &ExampleResult| e.res))
+            .collect(),
+    ))
 }
 
-// Select examples from Html
-fn select_samples(html: &Html) -> Vec<String> {
-    let selector: Selector = Selector::parse(r#"span[class="lang-ja"] h3 + pre"#).unwrap();
+// Get examples, time limit and metadata of the task from cache, or download them if they are not cached
+//
+// If downloading fails or `offline` is set, the shared cache is used even if it is old.
+// The caches are ignored if `refresh` is set
+async fn load_task(
+    task: &Task,
+    config: &Config,
+    offline: bool,
+    refresh: bool,
+) -> Result<TaskCache> {
+    if let Some(cache) = task_cache(task).filter(|_| !refresh) {
+        return Ok(cache);
+    }
+
+    let global_cache: bool = config.global_cache;
+
+    if global_cache && !offline && !refresh {
+        if let Some(cache) = global_task_cache(task, config.global_cache_ttl) {
+            save_task_cache(task, &cache)?;
+
+            return Ok(cache);
+        }
+    }
+
+    let downloaded: Option<Result<TaskCache>> = if offline {
+        None
+    } else {
+        Some(download_task(&make_client(), task).await)
+    };
+
+    let (downloaded, download_err): (Option<TaskCache>, Option<anyhow::Error>) = match downloaded {
+        Some(Ok(v)) => (Some(v), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
 
-    let mut samples: Vec<String> = Vec::new();
+    let cache: Option<TaskCache> = downloaded.or_else(|| global_task_cache(task, u64::MAX));
 
-    for i in html.select(&selector) {
-        let v: Vec<&str> = i.text().collect();
-        samples.push(v.join(""));
+    // The cache of the old format is not saved again, so that it is downloaded next time
+    let old: Option<TaskCache> = cache.is_none().then(|| old_task_cache(task)).flatten();
+
+    if let Some(e) = download_err {
+        if cache.is_none() && old.is_none() {
+            return Err(e.context(format!(
+                "Failed to download {}, and it is not cached",
+                task.url()
+            )));
+        }
+
+        eprintln!("{} Failed to download {}: {}", Marker::Minus, task.url(), e);
+    }
+
+    if let Some(old) = old {
+        return Ok(old);
     }
 
-    samples
+    let Some(cache) = cache else {
+        bail!(AttestError::Network(format!(
+            "No cached examples of {}. Please connect to the network",
+            task.id
+        )));
+    };
+
+    save_task_cache(task, &cache)?;
+
+    if global_cache && !offline {
+        save_task_cache_in(global_task_dir(task)?, &cache)?;
+    }
+
+    Ok(cache)
 }
 
 // Input and Output of example
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct IO {
+pub struct IO {
     pub input: String,
     pub output: String,
 }
@@ -136,396 +212,543 @@ impl IO {
     }
 }
 
-// Packing inputs and outputs to `IO`
-fn packing_to_io(v: &[String]) -> Vec<IO> {
-    v.chunks(2)
-        .map(|l: &[String]| IO::new(l[0].clone(), l[1].clone()))
-        .collect()
+// Hashes of the build steps which succeeded last time
+pub const BUILD_STEPS_CACHE: &str = "./.attest/build_steps.json";
+
+// Hash of the command and the inputs of the step and the hash of the steps before it
+//
+// None if the step has no inputs, which always runs, or some of the inputs don't exist
+fn step_hash(step: &BuildStep, chain: u64) -> Option<u64> {
+    if step.inputs.is_empty() {
+        return None;
+    }
+
+    let contents: Vec<Vec<u8>> = step
+        .inputs
+        .iter()
+        .map(fs::read)
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    Some(hash_from(&(&step.command, &step.inputs, contents, chain)))
 }
 
-// Get time limit
-fn get_time_limit(html: &Html) -> u128 {
-    let selector: Selector = Selector::parse(r#"div[class="col-sm-12"] > p"#).unwrap();
+// Hash of the steps up to the step, so that the steps after it run again when it changes
+fn chain_hash(chain: u64, step: &BuildStep, hash: Option<u64>) -> u64 {
+    let outputs: Vec<Option<Vec<u8>>> = step
+        .outputs
+        .iter()
+        .map(|p: &String| fs::read(p).ok())
+        .collect();
 
-    let t: &str = html
-        .select(&selector)
-        .next()
-        .unwrap()
-        .text()
-        .next()
-        .unwrap();
-
-    let re1: Regex = Regex::new("Time Limit: (.+) sec").unwrap();
-    let re2: Regex = Regex::new("Time Limit: (.+) msec").unwrap();
-
-    if let Some(s) = re1.captures(t) {
-        (s.get(1).unwrap().as_str().parse::<f64>().unwrap() * 1000.) as u128
-    } else if let Some(s) = re2.captures(t) {
-        s.get(1)
-            .unwrap_or_else(|| panic!("{} Please input the question page.", Marker::Minus))
-            .as_str()
-            .parse::<u128>()
-            .unwrap()
-    } else {
-        eprintln!("{}", Marker::minus("Failed to get time limit"));
-        u128::MAX
+    hash_from(&(chain, hash, outputs))
+}
+
+// How the build step ended
+#[derive(Debug)]
+pub enum BuildStatus {
+    Exited(Output),
+    // Killed after the seconds of `build_timeout`
+    Timeout(u64),
+}
+
+// Result of the build step which ran last
+#[derive(Debug)]
+pub struct BuildOutput {
+    // Name of the step, or None if the build is one command
+    pub step: Option<String>,
+    pub status: BuildStatus,
+    // Stderr of the steps which succeeded
+    pub warnings: Vec<String>,
+}
+
+// Steps restored from the artifact cache and the step which ran last
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    // Names of the restored steps, or None if the build is one command
+    pub restored: Vec<Option<String>>,
+    pub last: Option<BuildOutput>,
+}
+
+impl BuildOutput {
+    pub fn success(&self) -> bool {
+        matches!(&self.status, BuildStatus::Exited(output) if output.status.success())
+    }
+
+    pub fn stderr(&self) -> String {
+        match &self.status {
+            BuildStatus::Exited(output) => String::from_utf8_lossy(&output.stderr).into_owned(),
+            BuildStatus::Timeout(_) => String::new(),
+        }
+    }
+
+    // Why the build failed when the stderr doesn't tell it
+    pub fn reason(&self) -> Option<String> {
+        match &self.status {
+            BuildStatus::Timeout(secs) => Some(format!(
+                "The build timed out after {} seconds. Change it by build_timeout in attest.toml",
+                secs
+            )),
+            BuildStatus::Exited(output) => match signal_of(&output.status) {
+                Some(9) => Some(String::from(
+                    "The build was killed by signal 9. It may run out of memory",
+                )),
+                Some(signal) => Some(format!("The build was killed by signal {}", signal)),
+                None => None,
+            },
+        }
+    }
+
+    // Number of warnings like `main.cpp:3:5: warning: ...` in the stderr of the succeeded steps
+    pub fn warning_count(&self) -> usize {
+        self.warnings
+            .iter()
+            .flat_map(|s: &String| s.lines())
+            .filter(|line: &&str| WARNING.is_match(line) && !line.contains(" generated "))
+            .count()
     }
 }
 
-// Check if the code is same
-fn is_same_code(setting_toml: &Map<String, Value>) -> Option<bool> {
-    let file_path: &str = setting_toml
-        .get("file_path")
-        .unwrap_or_else(|| {
-            panic!(
-                "{}",
-                Marker::minus(r#"the "file_path" value does not exist"#)
-            )
-        })
-        .as_str()
-        .unwrap_or_else(|| {
-            panic!(
-                "{}",
-                Marker::minus(r#"the "file_path" value has to be string"#)
-            )
-        });
+// Line of a warning like `main.cpp:3:5: warning: ...`
+static WARNING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?im)^(?:\S+: )?warning\b").unwrap());
 
-    if !Path::new(file_path).is_file() {
-        return None;
+// Kill the process group led by the process with all processes it spawned
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    // SAFETY: killpg only sends a signal, and the group is the one the build was spawned in
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
     }
+}
 
-    let now_code: String = file_read_to_string(file_path);
+#[cfg(not(unix))]
+fn kill_group(_: u32) {}
 
-    let now_hash: u64 = hash_from(&now_code);
+// Signal which killed the process
+#[cfg(unix)]
+fn signal_of(status: &ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(status)
+}
 
-    let before: String = file_read_to_string("./.attest/before.txt");
+#[cfg(not(unix))]
+fn signal_of(_: &ExitStatus) -> Option<i32> {
+    None
+}
 
-    let before_hash: Option<u64> = match before.trim().parse() {
-        Ok(x) => Some(x),
-        Err(e) => match e.kind() {
-            IntErrorKind::Empty => None,
-            _ => return None,
-        },
+// Message of CE with where the build failed, why it failed and the stderr
+pub fn render_ce(build: &BuildOutput, file: Option<&str>) -> String {
+    let place: String = match (file, build.step.as_deref()) {
+        (Some(file), Some(step)) => format!(" in {} ({})", file, step),
+        (Some(place), None) | (None, Some(place)) => format!(" in {}", place),
+        (None, None) => String::new(),
     };
 
-    if Some(now_hash) == before_hash {
-        Some(true)
-    } else {
-        let mut f: File = File::create("./.attest/before.txt")
-            .unwrap_or_else(|_| panic!("{}", create_err("./.attest/before.txt")));
-        write!(&mut f, "{}", now_hash)
-            .unwrap_or_else(|_| panic!("{}", write_err("./.attest/before.txt")));
-        Some(false)
+    let mut buf: Vec<String> = vec![format!("{} \x1b[33mCE\x1b[m{}\n", Marker::Minus, place)];
+
+    if let Some(reason) = build.reason() {
+        buf.push(format!("{} {}", Marker::X, reason));
     }
+
+    buf.push(format!("{} stderr:\n{}", Marker::X, build.stderr()));
+
+    buf.join("\n")
 }
 
-fn is_same_deps_files(setting_toml: &Map<String, Value>) -> bool {
-    let Some(file_paths) = setting_toml.get("deps_files") else {
-        return true;
-    };
+// Run the build steps whose command or inputs are changed, and stop at the step which fails
+//
+// The outputs built before with the same command and inputs are restored from the artifact cache
+// instead of running the step. All steps run if `p_build` is true
+pub async fn build<T: AsRef<Path>>(config: &Config, dir: T, p_build: bool) -> Result<BuildReport> {
+    let is_steps: bool = matches!(config.build, Build::Steps(_));
 
-    let files = if let Some(l) = file_paths.as_array() {
-        l.iter().map(|v: &Value| {
-            v.as_str().unwrap_or_else(|| {
-                panic!(
-                    "{} The values of deps_files array have to be str",
-                    Marker::Minus
-                )
-            })
-        })
-    } else {
-        eprintln!("{} The deps_files value has to be array", Marker::Minus);
-        return true;
-    };
+    let before: BTreeMap<String, u64> = fs::read_to_string(BUILD_STEPS_CACHE)
+        .ok()
+        .and_then(|s: String| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
 
-    let file_hashes = files.map(|p: &str| {
-        let f: String = file_read_to_string(p);
-        (p, hash_from(&f))
-    });
+    let mut caches: BTreeMap<String, u64> = BTreeMap::new();
 
-    let caches_text: String =
-        fs::read_to_string("./.attest/deps_caches.json").unwrap_or("{}".to_string());
+    let mut report: BuildReport = BuildReport::default();
 
-    let mut caches: HashMap<&str, u64> = serde_json::from_str(&caches_text).unwrap_or_default();
+    let mut warnings: Vec<String> = Vec::new();
 
-    let mut new_cache: HashMap<&str, u64> = HashMap::new();
+    let mut chain: u64 = 0;
 
-    let mut is_same: bool = true;
+    let mut prev: Option<(&BuildStep, Option<u64>)> = None;
 
-    for (key, hash) in file_hashes {
-        if !caches.get(&key).is_some_and(|v: &u64| *v == hash) {
-            is_same = false;
-        }
-        caches.remove(&key);
-        new_cache.insert(key, hash);
-    }
+    let steps: Vec<BuildStep> = config.build_steps();
 
-    is_same = is_same && caches.is_empty();
+    for (i, step) in steps.iter().enumerate() {
+        if let Some((prev_step, prev_hash)) = prev {
+            chain = chain_hash(chain, prev_step, prev_hash);
+        }
 
-    if !is_same {
-        let mut f: File = File::create("./.attest/deps_caches.json")
-            .unwrap_or_else(|_| panic!("{}", create_err("./.attest/deps_caches.json")));
+        let label: String = step.label(i);
 
-        let s: String = serde_json::to_string(&new_cache).unwrap();
-        write!(f, "{}", s)
-            .unwrap_or_else(|_| panic!("{}", write_err("./.attest/deps_caches.json")));
-    }
+        let [command, args @ ..] = step.command.as_slice() else {
+            continue;
+        };
 
-    is_same
-}
+        let hash: Option<u64> = step_hash(step, chain);
 
-fn is_same_setting(setting_toml: &Map<String, Value>) -> bool {
-    let before_settiing: Map<String, Value> = items_toml("./.attest/before_setting.toml");
+        prev = Some((step, hash));
 
-    if setting_toml == &before_settiing {
-        true
-    } else {
-        let mut f: File = File::create("./.attest/before_setting.toml")
-            .unwrap_or_else(|_| panic!("{}", create_err("./.attest/before_setting.toml")));
-        writeln!(&mut f, "{}", setting_toml)
-            .unwrap_or_else(|_| panic!("{}", write_err("./.attest/before_setting.toml")));
+        let has_outputs: bool = step.outputs.iter().all(|p: &String| Path::new(p).exists());
 
-        false
-    }
-}
+        if let Some(hash) = hash.filter(|_| !p_build) {
+            if before.get(&label) == Some(&hash) && has_outputs {
+                caches.insert(label, hash);
+                continue;
+            }
 
-fn build<T: AsRef<Path>>(
-    setting_toml: &Map<String, Value>,
-    dir: T,
-    p_build: bool,
-) -> Option<Output> {
-    if let Some(c) = setting_toml.get("build") {
-        if !p_build
-            && is_same_code(setting_toml).unwrap_or(true)
-            && is_same_setting(setting_toml)
-            && is_same_deps_files(setting_toml)
-        {
-            return None;
+            if restore_artifact(hash, &step.outputs)? {
+                report
+                    .restored
+                    .push(Some(label.clone()).filter(|_| is_steps));
+                caches.insert(label, hash);
+                continue;
+            }
         }
 
-        let build_commands: Vec<&str> = c
-            .as_array()
-            .unwrap_or_else(|| panic!("{}", Marker::minus(r#""build" value has to be array"#)))
-            .iter()
-            .map(|v: &Value| {
-                v.as_str().unwrap_or_else(|| {
-                    panic!(
-                        "{}",
-                        Marker::minus(r#"items of "build" value has to be string"#)
-                    )
-                })
-            })
-            .collect();
+        let spawn_err = || {
+            AttestError::Build(format!(
+                r#"the build command "{}" couldn't be run"#,
+                command
+            ))
+        };
+
+        let mut cmd: Command = Command::new(command);
+
+        cmd.args(args)
+            .current_dir(dir.as_ref())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        // The build leads its own process group, so that the timeout kills compilers it spawned
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let child: Child = cmd.spawn().with_context(spawn_err)?;
+
+        let pid: Option<u32> = child.id();
+
+        let output = child.wait_with_output();
+
+        let status: BuildStatus = if config.build_timeout == 0 {
+            BuildStatus::Exited(output.await.with_context(spawn_err)?)
+        } else {
+            match time::timeout(Duration::from_secs(config.build_timeout), output).await {
+                Ok(v) => BuildStatus::Exited(v.with_context(spawn_err)?),
+                Err(_) => {
+                    if let Some(pid) = pid {
+                        kill_group(pid);
+                    }
 
-        let [command, args @ ..] = build_commands.as_slice() else {
-            return None;
+                    BuildStatus::Timeout(config.build_timeout)
+                }
+            }
         };
 
-        Some(
-            StdCommand::new(command)
-                .args(args)
-                .current_dir(dir)
-                .output()
-                .unwrap_or_else(|_| {
-                    panic!(
-                        "{}",
-                        Marker::minus("Something went wrong when building program")
-                    )
-                }),
-        )
-    } else {
-        None
-    }
-}
+        let built: BuildOutput = BuildOutput {
+            step: Some(label.clone()).filter(|_| is_steps),
+            status,
+            warnings: Vec::new(),
+        };
 
-fn build_wrap<T: AsRef<Path>>(
-    setting_toml: &Map<String, Value>,
-    dir: T,
-    results: &mut Vec<Option<Res>>,
-    p_build: bool,
-) -> Result<(), String> {
-    let mut buf: String = String::new();
-    if let Some(output) = build(setting_toml, dir, p_build) {
-        if output.status.code().unwrap() != 0 {
-            writeln!(buf, "{} \x1b[33mCE\x1b[m\n", Marker::Minus).unwrap();
-            writeln!(
-                buf,
-                "{} stderr:\n{}",
-                Marker::X,
-                std::str::from_utf8(&output.stderr).unwrap()
-            )
-            .unwrap();
-            results.push(Some(Res::CE));
+        let success: bool = built.success();
+
+        let stderr: String = match command.as_str() {
+            "cargo" => strip_status(&built.stderr()),
+            _ => built.stderr(),
+        };
 
-            return Err(buf);
+        if success && !stderr.trim().is_empty() {
+            warnings.push(stderr);
         }
-    }
 
-    Ok(())
-}
+        if let Some(hash) = hash.filter(|_| success) {
+            if !step.outputs.is_empty()
+                && step.outputs.iter().all(|p: &String| Path::new(p).is_file())
+            {
+                save_artifact(hash, &step.outputs)?;
+            }
 
-fn get_string_list(key: &str, toml: &Map<String, Value>) -> Option<Vec<String>> {
-    Some(
-        toml.get(key)?
-            .as_array()
-            .unwrap_or_else(|| panic!(r#""{}" value has to be array"#, key))
-            .iter()
-            .map(|v: &Value| {
-                v.as_str()
-                    .unwrap_or_else(|| panic!(r#"items of "{}" value have to be string"#, key))
-                    .to_string()
-            })
-            .collect(),
-    )
-}
+            caches.insert(label.clone(), hash);
+        }
 
-fn get_commands(setting_toml: &Map<String, Value>) -> Vec<String> {
-    get_string_list("run", setting_toml).unwrap_or_else(|| {
-        panic!(
-            "{}",
-            Marker::minus(r#""attest.toml" must have "run" value"#)
-        )
-    })
-}
+        report.last = Some(BuildOutput {
+            warnings: warnings.clone(),
+            ..built
+        });
 
-fn get_test_command(setting_toml: &Map<String, Value>) -> Option<Vec<String>> {
-    get_string_list("test", setting_toml)
+        if !success {
+            break;
+        }
+    }
+
+    let mut f: File =
+        File::create(BUILD_STEPS_CACHE).with_context(|| create_err(BUILD_STEPS_CACHE))?;
+
+    write!(f, "{}", serde_json::to_string(&caches)?)
+        .with_context(|| write_err(BUILD_STEPS_CACHE))?;
+
+    Ok(report)
 }
 
-#[fastout]
-async fn tester(
-    examples: &[IO],
-    setting_toml: &Map<String, Value>,
-    time_limit: u128,
-    example_num: Vec<usize>,
+// Build the program and show the warnings, and get the message of CE if the build fails
+async fn build_wrap<T: AsRef<Path>>(
+    config: &Config,
+    dir: T,
     p_build: bool,
-) -> Option<Vec<Option<Res>>> {
-    let dir: Arc<PathBuf> = Arc::new(current_dir().unwrap());
+    show_warnings: bool,
+) -> Result<Option<String>> {
+    let report: BuildReport = build(config, dir, p_build).await?;
+
+    for step in &report.restored {
+        match step {
+            Some(label) => println!("{} Restored {} from the cache", Marker::X, label),
+            None => println!("{} Restored the build from the cache", Marker::X),
+        }
+    }
 
-    let mut results: Vec<Option<Res>> = Vec::new();
+    let Some(built) = report.last else {
+        return Ok(None);
+    };
 
-    if let Err(e) = build_wrap(setting_toml, &*dir, &mut results, p_build) {
-        println!("{}", e);
-        return None;
+    if !built.success() {
+        return Ok(Some(render_ce(&built, None)));
     }
 
-    let commands: Vec<String> = get_commands(setting_toml);
+    if show_warnings && !built.warnings.is_empty() {
+        println!(
+            "{} build warnings:\n{}",
+            Marker::X,
+            built.warnings.join("\n")
+        );
+    } else if built.warning_count() > 0 {
+        println!(
+            "{} The build has {} warnings. Show them with --warnings",
+            Marker::X,
+            built.warning_count()
+        );
+    }
 
-    let [excute_command, args @ ..] = commands.as_slice() else {
-        panic!(
-            "{}",
-            Marker::minus(r#"the length of "command" value has to be more than 0"#)
-        )
-    };
+    Ok(None)
+}
+
+// Commands to run and judge the program
+#[derive(Debug, Clone)]
+pub struct TestConfig {
+    pub run: Vec<String>,
+    pub test: Option<Vec<String>>,
+    // Directory to run the commands in
+    pub dir: PathBuf,
+}
 
-    let execute_command: Arc<String> = Arc::new(excute_command.clone());
+impl TestConfig {
+    // Get the commands from the settings
+    pub(crate) fn from_settings<T: AsRef<Path>>(config: &Config, dir: T) -> Result<Self> {
+        Ok(TestConfig {
+            run: config.run_command()?.to_vec(),
+            test: config.test.clone(),
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+}
 
-    let args: Arc<Vec<String>> = Arc::new(args.to_vec());
+// Result of running the program for an example
+#[derive(Debug, Clone)]
+pub struct ExampleResult {
+    // Number of the example starting from 1
+    pub number: usize,
+    pub res: Res,
+    pub io: IO,
+    pub output: String,
+    pub stderr: String,
+    // Milliseconds
+    pub time: u128,
+    // Description of the judge by the test command
+    pub description: Option<String>,
+}
 
-    let test_commands: Arc<Option<Vec<String>>> = Arc::new(get_test_command(setting_toml));
+// Run the program for the examples, or only for the numbers in `example_num` if it is not empty
+pub async fn tester(
+    examples: &[IO],
+    config: &TestConfig,
+    time_limit: u128,
+    example_num: &[usize],
+    meta: &ProblemMeta,
+) -> Vec<Result<ExampleResult>> {
+    let config: Arc<TestConfig> = Arc::new(config.clone());
 
     let mut handles = Vec::new();
 
     for (index, io) in examples.iter().enumerate() {
         if !example_num.is_empty() && !example_num.contains(&(index + 1)) {
-            handles.push(None);
             continue;
         }
 
         let io: IO = io.clone();
-        let args: Arc<Vec<String>> = Arc::clone(&args);
-
-        let test_commands: Arc<Option<Vec<String>>> = Arc::clone(&test_commands);
-        let dir: Arc<PathBuf> = Arc::clone(&dir);
-        let execute_command: Arc<String> = Arc::clone(&execute_command);
-
-        let f = async move {
-            let mut buf: String = String::new();
+        let config: Arc<TestConfig> = Arc::clone(&config);
+        let meta: ProblemMeta = meta.clone();
+
+        handles.push(tokio::spawn(async move {
+            run_example(index + 1, io, &config, time_limit, &meta)
+                .await
+                .with_context(|| format!("example{}", index + 1))
+        }));
+    }
 
-            writeln!(buf, "{} \x1b[35mexample{}\x1b[m", Marker::X, index + 1)?;
+    let mut results: Vec<Result<ExampleResult>> = Vec::new();
 
-            let output = spawn_command(&io.input, &*dir, &execute_command, args.as_slice()).await?;
+    for handle in handles {
+        results.push(handle.await.unwrap_or_else(|e| Err(e.into())));
+    }
 
-            let start: Instant = Instant::now();
+    results
+}
 
-            let output: Output =
-                match time::timeout(Duration::from_millis(time_limit as u64), output).await {
-                    Ok(v) => v?,
-                    Err(_) => {
-                        let time: u128 = start.elapsed().as_millis();
+// Run the program for the example and judge the output
+async fn run_example(
+    number: usize,
+    io: IO,
+    config: &TestConfig,
+    time_limit: u128,
+    meta: &ProblemMeta,
+) -> Result<ExampleResult> {
+    let (execute_command, args): (&String, &[String]) =
+        config.run.split_first().ok_or_else(|| {
+            AttestError::Config(String::from(
+                r#"the length of "run" value has to be more than 0"#,
+            ))
+        })?;
+
+    let output = spawn_command(&io.input, &config.dir, execute_command, args).await?;
+
+    let start: Instant = Instant::now();
+
+    let output: Output = match time::timeout(Duration::from_millis(time_limit as u64), output).await
+    {
+        Ok(v) => v?,
+        Err(_) => {
+            return Ok(ExampleResult {
+                number,
+                res: Res::TLE,
+                io,
+                output: String::new(),
+                stderr: String::new(),
+                time: start.elapsed().as_millis(),
+                description: None,
+            })
+        }
+    };
 
-                        writeln!(buf, "{} \x1b[33mTLE\x1b[m\n", Marker::Minus)?;
+    let time: u128 = start.elapsed().as_millis();
 
-                        writeln!(buf, "{} input:\n{}", Marker::X, io.input)?;
-                        writeln!(buf, "{} correct output:\n{}", Marker::X, io.output)?;
+    check(number, output, time, io, &config.test, &config.dir, meta).await
+}
 
-                        writeln!(buf, "{} time: {}", Marker::X, time)?;
+// Show the results of the examples
+#[fastout]
+fn print_results(results: &[Result<ExampleResult>]) {
+    for result in results {
+        match result {
+            Ok(r) => println!("{}", render_result(r)),
+            Err(err) => {
+                eprintln!("{} \x1b[32mError\x1b[m", Marker::Minus);
+                eprintln!("{} Error message or detail", Marker::X);
+                eprintln!("{:#}", err)
+            }
+        }
+    }
 
-                        return Result::<(Res, String)>::Ok((Res::TLE, buf));
-                    }
-                };
+    for r in results.iter().flatten() {
+        println!(
+            "{} example{}: {}",
+            Marker::from(r.res),
+            r.number,
+            match r.res {
+                Res::AC => "\x1b[32mAC\x1b[m",
+                Res::WA => "\x1b[33mWA\x1b[m",
+                Res::MaybeWA => "\x1b[33mWA?\x1b[m (needs checker)",
+                Res::RE => "\x1b[33mRE\x1b[m",
+                Res::TLE => "\x1b[33mTLE\x1b[m",
+                _ => "",
+            }
+        );
+    }
+}
 
-            let time: u128 = start.elapsed().as_millis();
+// Render the result of the example as terminal text
+fn render_result(r: &ExampleResult) -> String {
+    let mut buf: Vec<String> = vec![format!("{} \x1b[35mexample{}\x1b[m", Marker::X, r.number)];
 
-            Ok((
-                check(output, time, &io, &test_commands, &*dir, &mut buf).await?,
-                buf,
-            ))
-        };
+    let description: Option<String> = r
+        .description
+        .as_ref()
+        .map(|d: &String| format!("{} discription:\n{}", Marker::X, d));
 
-        handles.push(Some(tokio::spawn(f)))
-    }
+    match r.res {
+        Res::TLE => {
+            buf.push(format!("{} \x1b[33mTLE\x1b[m\n", Marker::Minus));
+            buf.push(format!("{} input:\n{}", Marker::X, r.io.input));
+            buf.push(format!("{} correct output:\n{}", Marker::X, r.io.output));
+            buf.push(format!("{} time: {}", Marker::X, r.time));
 
-    for op_handle in handles {
-        if let Some(handle) = op_handle {
-            match handle.await.unwrap() {
-                Ok((res, test_result)) => {
-                    results.push(Some(res));
-                    println!("{}", test_result);
-                }
-                Err(err) => {
-                    results.push(None);
-                    eprintln!("{} \x1b[32mError\x1b[m", Marker::Minus);
-                    eprintln!("{} Error message or detail", Marker::X);
-                    eprintln!("{}", err)
-                }
-            }
-        } else {
-            results.push(None);
+            return buf.join("\n") + "\n";
         }
-    }
-
-    for (i, r) in results.iter().enumerate() {
-        if let Some(r) = r {
-            println!(
-                "{} example{}: {}",
-                Marker::from(r),
-                i + 1,
-                match r {
-                    Res::AC => "\x1b[32mAC\x1b[m",
-                    Res::WA => "\x1b[33mWA\x1b[m",
-                    Res::RE => "\x1b[33mRE\x1b[m",
-                    Res::TLE => "\x1b[33mTLE\x1b[m",
-                    _ => "",
-                }
-            );
+        Res::AC => {
+            buf.push(format!("{} \x1b[32mAC\x1b[m", Marker::Plus));
+            buf.extend(description);
+            buf.push(String::new());
+            buf.push(format!("{} input:\n{}", Marker::X, r.io.input));
+        }
+        Res::MaybeWA => {
+            buf.push(format!(
+                "{} \x1b[33mWA?\x1b[m (needs checker)",
+                Marker::Minus
+            ));
+            buf.push(String::new());
+            buf.push(format!("{} input:\n{}", Marker::X, r.io.input));
+            buf.push(format!("{} example output:\n{}", Marker::X, r.io.output));
+        }
+        Res::WA => {
+            buf.push(format!("{} \x1b[33mWA\x1b[m", Marker::Minus));
+            buf.extend(description);
+            buf.push(String::new());
+            buf.push(format!("{} input:\n{}", Marker::X, r.io.input));
+            buf.push(format!("{} correct output:\n{}", Marker::X, r.io.output));
+        }
+        Res::RE | Res::CE => {
+            buf.push(format!("{} \x1b[33mRE\x1b[m", Marker::Minus));
+            buf.push(format!("{} input:\n{}", Marker::X, r.io.input));
         }
     }
 
-    Some(results)
+    buf.push(format!("{} output:\n{}\n", Marker::X, r.output));
+    buf.push(format!("{} stderr:\n{}", Marker::X, r.stderr));
+    buf.push(format!("{} time: {}", Marker::X, r.time));
+    buf.push(String::new());
+
+    buf.join("\n") + "\n"
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Res {
     AC,
     WA,
+    // Different from the example, but the task may accept multiple answers
+    MaybeWA,
     CE,
     RE,
     #[allow(clippy::upper_case_acronyms)]
     TLE,
 }
 
-async fn spawn_command<T: AsRef<Path>>(
+pub async fn spawn_command<T: AsRef<Path>>(
     input: &str,
     dir: T,
     execute_command: &str,
@@ -611,64 +834,187 @@ async fn custom_judge<T: AsRef<Path>>(
     ))
 }
 
-async fn check<T: AsRef<Path>>(
+// Judge the output with the test command if it is set, otherwise by exact matching
+// or with the absolute or relative error `tolerance`
+pub async fn judge<T: AsRef<Path>>(
+    result: &str,
+    io: &IO,
+    test_command: &Option<Vec<String>>,
+    tolerance: Option<f64>,
+    dir: T,
+) -> Result<(bool, Option<String>)> {
+    if test_command
+        .as_ref()
+        .is_some_and(|c: &Vec<String>| !c.is_empty())
+    {
+        custom_judge(test_command, result, io, dir).await
+    } else if let Some(tolerance) = tolerance {
+        Ok((is_within_tolerance(result, &io.output, tolerance), None))
+    } else {
+        Ok((result == io.output, None))
+    }
+}
+
+// Check if each token is same, or within the absolute or relative error if both are numbers
+fn is_within_tolerance(result: &str, correct: &str, tolerance: f64) -> bool {
+    let result: Vec<&str> = result.split_whitespace().collect();
+    let correct: Vec<&str> = correct.split_whitespace().collect();
+
+    result.len() == correct.len()
+        && result.iter().zip(&correct).all(|(r, c): (&&str, &&str)| {
+            r == c
+                || match (r.parse::<f64>(), c.parse::<f64>()) {
+                    (Ok(r), Ok(c)) => {
+                        let error: f64 = (r - c).abs();
+                        error <= tolerance || error <= tolerance * c.abs()
+                    }
+                    _ => false,
+                }
+        })
+}
+
+// Judge the output of the program for the example
+pub async fn check<T: AsRef<Path>>(
+    number: usize,
     output: Output,
     time: u128,
-    io: &IO,
+    io: IO,
     test_command: &Option<Vec<String>>,
     dir: T,
-    buf: &mut String,
-) -> Result<Res> {
-    let result: &str = std::str::from_utf8(&output.stdout).unwrap_or("");
-
-    let return_value: Res = if output.status.code() == Some(0) {
-        let (condition, discription): (bool, Option<String>) =
-            if test_command.is_some() && !test_command.as_ref().unwrap().is_empty() {
-                custom_judge(test_command, result, io, dir).await?
-            } else {
-                (result == io.output, None)
-            };
-
-        let print_discription = |buf: &mut String| -> Result<()> {
-            if let Some(d) = discription {
-                writeln!(buf, "{} discription:\n{}", Marker::X, d)?;
-            }
-            Ok(())
-        };
+    meta: &ProblemMeta,
+) -> Result<ExampleResult> {
+    let result: String = String::from_utf8_lossy(&output.stdout).into_owned();
+
+    let (res, description): (Res, Option<String>) = if output.status.success() {
+        let (condition, description): (bool, Option<String>) =
+            judge(&result, &io, test_command, meta.tolerance, dir).await?;
 
         if condition {
-            writeln!(buf, "{} \x1b[32mAC\x1b[m", Marker::Plus)?;
-            print_discription(buf)?;
-            writeln!(buf)?;
-            writeln!(buf, "{} input:\n{}", Marker::X, io.input)?;
-            Res::AC
+            (Res::AC, description)
+        } else if meta.multiple_answers && test_command.is_none() {
+            (Res::MaybeWA, None)
         } else {
-            writeln!(buf, "{} \x1b[33mWA\x1b[m", Marker::Minus)?;
-            print_discription(buf)?;
-
-            writeln!(buf)?;
-
-            writeln!(buf, "{} input:\n{}", Marker::X, io.input)?;
-            writeln!(buf, "{} correct output:\n{}", Marker::X, io.output)?;
-            Res::WA
+            (Res::WA, description)
         }
     } else {
-        writeln!(buf, "{} \x1b[33mRE\x1b[m", Marker::Minus)?;
-        writeln!(buf, "{} input:\n{}", Marker::X, io.input)?;
-        Res::RE
+        (Res::RE, None)
     };
 
-    writeln!(buf, "{} output:\n{}\n", Marker::X, result)?;
+    Ok(ExampleResult {
+        number,
+        res,
+        io,
+        output: result,
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        time,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, process};
+
+    #[test]
+    fn tolerance_accepts_small_errors() {
+        assert!(is_within_tolerance("1.0000001\n", "1\n", 1e-6));
+        assert!(is_within_tolerance("1000000.5", "1000000", 1e-6));
+        assert!(is_within_tolerance("0.5 2", "0.5000001 2", 1e-6));
+    }
+
+    #[test]
+    fn tolerance_rejects_large_errors() {
+        assert!(!is_within_tolerance("1.001", "1", 1e-6));
+        assert!(!is_within_tolerance("-1", "1", 1e-6));
+    }
 
-    writeln!(
-        buf,
-        "{} stderr:\n{}",
-        Marker::X,
-        std::str::from_utf8(&output.stderr)?
-    )?;
+    #[test]
+    fn tolerance_compares_words() {
+        assert!(is_within_tolerance("Yes 3.0", "Yes 3", 1e-6));
+        assert!(!is_within_tolerance("No 3", "Yes 3", 1e-6));
+        assert!(!is_within_tolerance("1 2", "1", 1e-6));
+        assert!(!is_within_tolerance("nan", "1", 1e-6));
+    }
 
-    writeln!(buf, "{} time: {}", Marker::X, time)?;
-    writeln!(buf)?;
+    // Directory for the files of the test, which is empty at first
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir: PathBuf = env::temp_dir().join(format!("attest-{}-{}", name, process::id()));
 
-    Ok(return_value)
+        let _ = fs::remove_dir_all(&dir);
+
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn step(dir: &Path, command: &[&str]) -> BuildStep {
+        BuildStep {
+            name: None,
+            command: command.iter().map(|s: &&str| s.to_string()).collect(),
+            inputs: vec![dir.join("main.cpp").display().to_string()],
+            outputs: vec![dir.join("a.out").display().to_string()],
+        }
+    }
+
+    #[test]
+    fn step_hash_changes_with_command_inputs_and_chain() {
+        let dir: PathBuf = temp_dir("step-hash");
+
+        fs::write(dir.join("main.cpp"), "int main() {}").unwrap();
+
+        let compile: BuildStep = step(&dir, &["g++", "main.cpp"]);
+
+        let hash: Option<u64> = step_hash(&compile, 0);
+
+        assert!(hash.is_some());
+        assert_eq!(step_hash(&compile, 0), hash);
+        assert_ne!(step_hash(&compile, 1), hash);
+        assert_ne!(step_hash(&step(&dir, &["g++", "-O2", "main.cpp"]), 0), hash);
+
+        fs::write(dir.join("main.cpp"), "int main() { return 0; }").unwrap();
+
+        assert_ne!(step_hash(&compile, 0), hash);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn step_without_inputs_has_no_hash() {
+        let dir: PathBuf = temp_dir("no-inputs");
+
+        let mut compile: BuildStep = step(&dir, &["g++", "main.cpp"]);
+
+        assert_eq!(step_hash(&compile, 0), None);
+
+        compile.inputs.clear();
+
+        assert_eq!(step_hash(&compile, 0), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chain_hash_changes_with_outputs() {
+        let dir: PathBuf = temp_dir("chain-hash");
+
+        let compile: BuildStep = step(&dir, &["g++", "main.cpp"]);
+
+        let missing: u64 = chain_hash(0, &compile, Some(1));
+
+        fs::write(dir.join("a.out"), "binary").unwrap();
+
+        let built: u64 = chain_hash(0, &compile, Some(1));
+
+        assert_ne!(built, missing);
+        assert_eq!(chain_hash(0, &compile, Some(1)), built);
+        assert_ne!(chain_hash(0, &compile, None), built);
+
+        fs::write(dir.join("a.out"), "another binary").unwrap();
+
+        assert_ne!(chain_hash(0, &compile, Some(1)), built);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
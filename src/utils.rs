@@ -2,13 +2,17 @@ use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::io::{prelude::*, ErrorKind};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{
     fs::{read_to_string, File},
     sync::Arc,
 };
 
+use crate::cache::current_task;
+use crate::error::AttestError;
+use crate::task::Task;
 use crate::test::Res;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use reqwest::cookie::Jar;
 use reqwest::{Client, Response, Url};
 use rustc_hash::FxHasher;
@@ -16,25 +20,32 @@ use scraper::html::Select;
 use scraper::{ElementRef, Html, Selector};
 use toml::{map::Map, Table, Value};
 
-pub fn create_err<P: AsRef<Path>>(path: P) -> String {
-    format!(
-        "\x1b[31m[-]\x1b[m something went wrong creating {}",
+pub fn create_err<P: AsRef<Path>>(path: P) -> AttestError {
+    AttestError::Io(format!(
+        "something went wrong creating {}",
         path.as_ref().display()
-    )
+    ))
 }
 
-pub fn write_err<P: AsRef<Path>>(path: P) -> String {
-    format!(
-        "\x1b[31m[-]\x1b[m something went wrong writing to {}",
+pub fn write_err<P: AsRef<Path>>(path: P) -> AttestError {
+    AttestError::Io(format!(
+        "something went wrong writing to {}",
         path.as_ref().display()
-    )
+    ))
 }
 
 // Request the link
 pub async fn request(client: &Client, url: &str) -> Result<String> {
-    let response: Response = client.get(url).send().await?;
+    let response: Response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| AttestError::Network(format!("failed to connect to {}", url)))?;
 
-    let body: String = response.text().await?;
+    let body: String = response
+        .text()
+        .await
+        .with_context(|| AttestError::Network(format!("failed to read {}", url)))?;
 
     Ok(body)
 }
@@ -44,12 +55,16 @@ pub fn to_html(text: String) -> Html {
     Html::parse_document(&text)
 }
 
+// Client with the session saved by `attest login`, which is not logged in without it
 pub fn make_client() -> Client {
-    let cookies: Arc<Jar> = Arc::new(Jar::default());
+    client_with_cookies(&get_cookies())
+}
 
-    let cookies_string: String = get_cookies();
+// Client with the cookies of AtCoder like `REVEL_SESSION=...`
+pub fn client_with_cookies(cookies_string: &str) -> Client {
+    let cookies: Arc<Jar> = Arc::new(Jar::default());
 
-    cookies.add_cookie_str(&cookies_string, &Url::parse("https://atcoder.jp").unwrap());
+    cookies.add_cookie_str(cookies_string, &Url::parse("https://atcoder.jp").unwrap());
 
     Client::builder()
         .cookie_store(true)
@@ -58,29 +73,39 @@ pub fn make_client() -> Client {
         .unwrap()
 }
 
-pub fn get_cookies() -> String {
-    let mut dir: PathBuf = dirs::home_dir().unwrap();
-
-    dir.push(".attest_global/cookies.txt");
+// Directory to hold the settings and caches shared across projects
+pub fn global_dir() -> Result<PathBuf> {
+    let home: PathBuf = dirs::home_dir().ok_or_else(|| {
+        AttestError::Io(String::from(
+            "the home directory was not found. Set the HOME environment variable",
+        ))
+    })?;
 
-    file_read_to_string(dir)
+    Ok(home.join(".attest_global"))
 }
 
-pub fn set_item_toml<T: AsRef<Path>>(path: T, key: &str, value: Value) {
-    let setting: String = file_read_to_string(&path);
+// Get cookies, which are empty if not logged in
+pub fn get_cookies() -> String {
+    let Ok(dir) = global_dir() else {
+        return String::new();
+    };
 
-    let mut f: File = File::create(&path).unwrap_or_else(|_| panic!("{}", create_err(&path)));
+    read_to_string(dir.join("cookies.txt")).unwrap_or_default()
+}
 
-    let mut setting_toml: Map<String, Value> = setting
-        .parse::<Table>()
-        .unwrap_or_else(|_| panic!("{}", Marker::minus(r#""attest.toml" has wrong format"#)));
+pub fn set_item_toml<T: AsRef<Path> + Debug>(path: T, key: &str, value: Value) -> Result<()> {
+    let mut setting_toml: Map<String, Value> = items_toml(&path)?;
 
     setting_toml.insert(String::from(key), value);
 
-    write!(&mut f, "{}", setting_toml).unwrap_or_else(|_| panic!("{}", write_err(&path)));
+    let mut f: File = File::create(&path).with_context(|| create_err(&path))?;
+
+    write!(&mut f, "{}", setting_toml).with_context(|| write_err(&path))?;
+
+    Ok(())
 }
 
-pub fn lang_select(html: &Html) -> Vec<(String, String)> {
+pub fn lang_select(html: &Html) -> Result<Vec<(String, String)>> {
     let selector: Selector = Selector::parse(r#"option"#).unwrap();
 
     let selected: Select = html.select(&selector);
@@ -88,44 +113,53 @@ pub fn lang_select(html: &Html) -> Vec<(String, String)> {
     selected
         .skip(1)
         .map(|i: ElementRef| {
-            (
-                i.text().next().unwrap().to_string(),
-                i.attr("value").unwrap().to_string(),
-            )
+            let name: &str = i.text().next().ok_or_else(|| {
+                AttestError::Scrape(String::from("a lang without its name was found"))
+            })?;
+
+            let value: &str = i
+                .attr("value")
+                .ok_or_else(|| AttestError::Scrape(format!("the lang {} has no ID", name)))?;
+
+            Ok((name.to_string(), value.to_string()))
         })
         .collect()
 }
 
-pub fn items_toml<T: AsRef<Path> + Debug>(path: T) -> Map<String, Value> {
-    let text: String = file_read_to_string(&path);
+pub fn items_toml<T: AsRef<Path> + Debug>(path: T) -> Result<Map<String, Value>> {
+    let text: String = file_read_to_string(&path)?;
+
+    let items: Map<String, Value> = text.parse::<Table>().map_err(|e: toml::de::Error| {
+        AttestError::Config(format!("{:?} has wrong format: {}", path, e.message()))
+    })?;
 
-    text.parse::<Table>()
-        .unwrap_or_else(|_| panic!("{} {:?} has wrong format", Marker::Minus, path))
+    Ok(items)
 }
 
-pub fn get_item_toml<T: AsRef<Path> + Debug>(path: T, key: &str) -> Option<Value> {
-    let items: Map<String, Value> = items_toml(path);
+pub fn get_item_toml<T: AsRef<Path> + Debug>(path: T, key: &str) -> Result<Option<Value>> {
+    let items: Map<String, Value> = items_toml(path)?;
 
-    Some(items.get(key)?.to_owned())
+    Ok(items.get(key).cloned())
 }
 
-pub fn file_read_to_string<T: AsRef<Path> + Clone>(path: T) -> String {
-    read_to_string(&path).unwrap_or_else(|e| match e.kind() {
-        ErrorKind::NotFound => {
-            File::create(&path).unwrap_or_else(|_| panic!("{}", create_err(&path)));
-            String::new()
+pub fn file_read_to_string<T: AsRef<Path>>(path: T) -> Result<String> {
+    match read_to_string(&path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            File::create(&path).with_context(|| create_err(&path))?;
+            Ok(String::new())
         }
-        e => panic!(
-            "{} {} couldn't be opened. Error: {}",
-            Marker::Minus,
-            path.as_ref().display(),
-            e
-        ),
-    })
+        Err(e) => Err(e).with_context(|| {
+            AttestError::Io(format!("{} couldn't be opened", path.as_ref().display()))
+        }),
+    }
 }
 
+// Get the link of the last task
 pub fn link_from_copy() -> String {
-    file_read_to_string("./.attest/url.txt")
+    current_task()
+        .map(|task: Task| task.url())
+        .unwrap_or_default()
 }
 
 #[derive(Clone, Copy, Hash, Debug)]
@@ -188,3 +222,10 @@ pub fn hash_from<T: Hash>(src: &T) -> u64 {
 
     hasher.finish()
 }
+
+// Seconds since UNIX epoch
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d: Duration| d.as_secs())
+}